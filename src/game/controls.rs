@@ -0,0 +1,765 @@
+//! The keys the game is played with. Every key press is turned into an
+//! `Action` by a frontend's `Keyboard`, so the rest of the game never looks
+//! at keys. The `Keyboard` also owns the screen where the keys are changed.
+//!
+//! The controls are kept in a small text file with a `4d-maze-controls
+//! <version>` line followed by one `action = Key, Key` line for each action.
+//! Every action is written out so the file shows all the keys, but a line
+//! can be left out to keep that action on its default keys. Lines starting
+//! with `#` are ignored. The first key of an action is the one the game
+//! tells the player to press and the rest are alternates, so
+//! `move_up = Up, W` lets the player use WASD as well as the arrow keys
+use crate::Move;
+use piston::input::keyboard::Key;
+use std::{
+    error::Error,
+    fmt, fs, io,
+    path::{Path, PathBuf},
+};
+
+/// Where the controls are kept
+pub const DEFAULT_CONTROLS_FILE: &str = "fourD_maze.controls";
+
+const CONTROLS_HEADER: &str = "4d-maze-controls 1";
+
+/// The keys that can be bound. The file uses the same names as piston
+const KEYS: [Key; 86] = [
+    Key::A,
+    Key::B,
+    Key::C,
+    Key::D,
+    Key::E,
+    Key::F,
+    Key::G,
+    Key::H,
+    Key::I,
+    Key::J,
+    Key::K,
+    Key::L,
+    Key::M,
+    Key::N,
+    Key::O,
+    Key::P,
+    Key::Q,
+    Key::R,
+    Key::S,
+    Key::T,
+    Key::U,
+    Key::V,
+    Key::W,
+    Key::X,
+    Key::Y,
+    Key::Z,
+    Key::D0,
+    Key::D1,
+    Key::D2,
+    Key::D3,
+    Key::D4,
+    Key::D5,
+    Key::D6,
+    Key::D7,
+    Key::D8,
+    Key::D9,
+    Key::F1,
+    Key::F2,
+    Key::F3,
+    Key::F4,
+    Key::F5,
+    Key::F6,
+    Key::F7,
+    Key::F8,
+    Key::F9,
+    Key::F10,
+    Key::F11,
+    Key::F12,
+    Key::Left,
+    Key::Right,
+    Key::Up,
+    Key::Down,
+    Key::PageUp,
+    Key::PageDown,
+    Key::Home,
+    Key::End,
+    Key::Insert,
+    Key::Delete,
+    Key::Backspace,
+    Key::Tab,
+    Key::Return,
+    Key::Escape,
+    Key::Space,
+    Key::Minus,
+    Key::Equals,
+    Key::Comma,
+    Key::Period,
+    Key::Slash,
+    Key::LShift,
+    Key::RShift,
+    Key::LCtrl,
+    Key::RCtrl,
+    Key::NumPad0,
+    Key::NumPad1,
+    Key::NumPad2,
+    Key::NumPad3,
+    Key::NumPad4,
+    Key::NumPad5,
+    Key::NumPad6,
+    Key::NumPad7,
+    Key::NumPad8,
+    Key::NumPad9,
+    Key::NumPadPlus,
+    Key::NumPadMinus,
+    Key::NumPadEnter,
+    Key::Backquote,
+];
+
+/// Something the player can do
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Action {
+    MoveLeft,
+    MoveRight,
+    MoveUp,
+    MoveDown,
+    /// Up the depth axis of the plane
+    DepthUp,
+    /// Down the depth axis of the plane
+    DepthDown,
+    /// Turn onto the next plane
+    RotateForward,
+    /// Turn back onto the previous plane
+    RotateBack,
+    Undo,
+    Redo,
+    Hint,
+    /// Stop and start the clocks
+    Pause,
+    /// Show or hide the high scores
+    Scores,
+    Save,
+    /// Open the screen where the keys are changed
+    Rebind,
+}
+
+/// The keys bound to each action
+#[derive(Clone, Debug, PartialEq)]
+pub struct Controls {
+    bindings: Vec<(Action, Vec<Key>)>,
+}
+
+/// Everything that can be wrong with the controls file
+#[derive(Debug, PartialEq)]
+pub enum ControlsError {
+    /// The file doesn't start with the controls header
+    NotControls,
+    /// The line (counting from 1) isn't `action = keys`
+    Malformed(usize),
+    /// The line names an action that doesn't exist
+    UnknownAction(usize, String),
+    /// The line names a key that can't be bound
+    UnknownKey(usize, String),
+    /// The key is bound to two different actions
+    Conflict(Key, Action, Action),
+}
+
+impl Action {
+    /// Every action, in the order they are listed
+    pub const ALL: [Action; 15] = [
+        Action::MoveLeft,
+        Action::MoveRight,
+        Action::MoveUp,
+        Action::MoveDown,
+        Action::DepthUp,
+        Action::DepthDown,
+        Action::RotateForward,
+        Action::RotateBack,
+        Action::Undo,
+        Action::Redo,
+        Action::Hint,
+        Action::Pause,
+        Action::Scores,
+        Action::Save,
+        Action::Rebind,
+    ];
+
+    /// The name of the action in the controls file
+    pub fn name(self) -> &'static str {
+        match self {
+            Action::MoveLeft => "move_left",
+            Action::MoveRight => "move_right",
+            Action::MoveUp => "move_up",
+            Action::MoveDown => "move_down",
+            Action::DepthUp => "depth_up",
+            Action::DepthDown => "depth_down",
+            Action::RotateForward => "rotate_forward",
+            Action::RotateBack => "rotate_back",
+            Action::Undo => "undo",
+            Action::Redo => "redo",
+            Action::Hint => "hint",
+            Action::Pause => "pause",
+            Action::Scores => "scores",
+            Action::Save => "save",
+            Action::Rebind => "rebind",
+        }
+    }
+
+    /// The action called `name` in the controls file
+    pub fn from_name(name: &str) -> Option<Self> {
+        Action::ALL
+            .iter()
+            .cloned()
+            .find(|action| action.name() == name)
+    }
+
+    /// The move this action makes, if it is a movement
+    pub fn player_move(self) -> Option<Move> {
+        match self {
+            Action::MoveLeft => Some(Move::Left),
+            Action::MoveRight => Some(Move::Right),
+            Action::MoveUp => Some(Move::Up),
+            Action::MoveDown => Some(Move::Down),
+            Action::DepthUp => Some(Move::DepthUp),
+            Action::DepthDown => Some(Move::DepthDown),
+            _ => None,
+        }
+    }
+
+    /// The keys the action is on if there is no controls file. PageUp and
+    /// PageDown move along the depth axis, and the slice is then cut at the
+    /// new position so it shows the next layer of the maze
+    fn default_keys(self) -> Vec<Key> {
+        match self {
+            Action::MoveLeft => vec![Key::Left],
+            Action::MoveRight => vec![Key::Right],
+            Action::MoveUp => vec![Key::Up],
+            Action::MoveDown => vec![Key::Down],
+            Action::DepthUp => vec![Key::PageUp],
+            Action::DepthDown => vec![Key::PageDown],
+            Action::RotateForward => vec![Key::D],
+            Action::RotateBack => vec![Key::A],
+            Action::Undo => vec![Key::U],
+            Action::Redo => vec![Key::R],
+            Action::Hint => vec![Key::H],
+            Action::Pause => vec![Key::P],
+            Action::Scores => vec![Key::L],
+            Action::Save => vec![Key::F5],
+            Action::Rebind => vec![Key::K],
+        }
+    }
+}
+
+impl From<Move> for Action {
+    fn from(player_move: Move) -> Self {
+        match player_move {
+            Move::Left => Action::MoveLeft,
+            Move::Right => Action::MoveRight,
+            Move::Up => Action::MoveUp,
+            Move::Down => Action::MoveDown,
+            Move::DepthUp => Action::DepthUp,
+            Move::DepthDown => Action::DepthDown,
+        }
+    }
+}
+
+impl Controls {
+    /// Loads the controls from `path`. A missing file means the default
+    /// keys. A file that can't be read is left alone for the player to fix
+    /// and the default keys are used until then
+    pub fn load<P: AsRef<Path>>(path: P) -> Self {
+        let path = path.as_ref();
+        let result = match fs::read_to_string(path) {
+            Ok(text) => Self::from_text(&text),
+            Err(ref error) if error.kind() == io::ErrorKind::NotFound => return Self::default(),
+            Err(error) => {
+                eprintln!("Could not read the controls: {}", error);
+                return Self::default();
+            }
+        };
+
+        result.unwrap_or_else(|error| {
+            eprintln!(
+                "The controls in {} are broken, the default keys are used instead: {}",
+                path.display(),
+                error
+            );
+            Self::default()
+        })
+    }
+
+    /// Writes the controls to `path`
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        fs::write(path, self.to_text())
+    }
+
+    /// The action `key` is bound to
+    pub fn action(&self, key: Key) -> Option<Action> {
+        self.bindings
+            .iter()
+            .find(|(_, keys)| keys.contains(&key))
+            .map(|&(action, _)| action)
+    }
+
+    /// The keys bound to `action`, the main one first
+    pub fn keys(&self, action: Action) -> &[Key] {
+        self.bindings
+            .iter()
+            .find(|&&(other, _)| other == action)
+            .map_or(&[][..], |(_, keys)| keys.as_slice())
+    }
+
+    /// The name of the main key of `action`, to tell the player what to press
+    pub fn key_name(&self, action: Action) -> String {
+        self.keys(action)
+            .first()
+            .map_or_else(|| "(unbound)".to_string(), |key| format!("{:?}", key))
+    }
+
+    /// Moves `action` onto `key` alone, dropping its alternates
+    pub fn bind(&mut self, action: Action, key: Key) -> Result<(), ControlsError> {
+        self.check_free(action, key)?;
+        self.keys_mut(action).clear();
+        self.keys_mut(action).push(key);
+        Ok(())
+    }
+
+    /// Adds `key` as an alternate for `action`
+    pub fn add(&mut self, action: Action, key: Key) -> Result<(), ControlsError> {
+        self.check_free(action, key)?;
+        let keys = self.keys_mut(action);
+        if !keys.contains(&key) {
+            keys.push(key);
+        }
+        Ok(())
+    }
+
+    /// A key can only do one thing
+    fn check_free(&self, action: Action, key: Key) -> Result<(), ControlsError> {
+        match self.action(key) {
+            Some(other) if other != action => Err(ControlsError::Conflict(key, other, action)),
+            _ => Ok(()),
+        }
+    }
+
+    fn keys_mut(&mut self, action: Action) -> &mut Vec<Key> {
+        let index = Action::ALL
+            .iter()
+            .position(|&other| other == action)
+            .unwrap();
+        &mut self.bindings[index].1
+    }
+
+    fn to_text(&self) -> String {
+        let mut text = format!("{}\n# action = key, alternate keys\n", CONTROLS_HEADER);
+        for (action, keys) in &self.bindings {
+            let keys = keys
+                .iter()
+                .map(|key| format!("{:?}", key))
+                .collect::<Vec<_>>();
+            text += &format!("{} = {}\n", action.name(), keys.join(", "));
+        }
+        text
+    }
+
+    fn from_text(text: &str) -> Result<Self, ControlsError> {
+        let mut lines = text.lines().map(str::trim);
+        if lines.next() != Some(CONTROLS_HEADER) {
+            return Err(ControlsError::NotControls);
+        }
+
+        let mut controls = Self::default();
+        let mut bound = Vec::new();
+        for (number, line) in (2..).zip(lines) {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let mut parts = line.splitn(2, '=').map(str::trim);
+            let (name, keys) = match (parts.next(), parts.next()) {
+                (Some(name), Some(keys)) => (name, keys),
+                _ => return Err(ControlsError::Malformed(number)),
+            };
+            let action = Action::from_name(name)
+                .ok_or_else(|| ControlsError::UnknownAction(number, name.to_string()))?;
+            let keys = keys
+                .split(',')
+                .map(str::trim)
+                .filter(|name| !name.is_empty())
+                .map(|name| {
+                    parse_key(name)
+                        .ok_or_else(|| ControlsError::UnknownKey(number, name.to_string()))
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+
+            *controls.keys_mut(action) = keys;
+            bound.push(action);
+        }
+
+        // Actions missing from the file keep their default keys, unless one
+        // of them was given to an action in the file
+        for &action in &Action::ALL {
+            if !bound.contains(&action) {
+                let keys = controls.keys_mut(action);
+                let taken = keys.clone();
+                keys.clear();
+                for key in taken {
+                    if controls.action(key).is_none() {
+                        controls.keys_mut(action).push(key);
+                    }
+                }
+            }
+        }
+        controls.validate()?;
+
+        Ok(controls)
+    }
+
+    /// Checks that no key is bound to two actions
+    pub fn validate(&self) -> Result<(), ControlsError> {
+        for (index, (action, keys)) in self.bindings.iter().enumerate() {
+            for &key in keys {
+                if let Some((other, _)) = self.bindings[index + 1..]
+                    .iter()
+                    .find(|(_, other_keys)| other_keys.contains(&key))
+                {
+                    return Err(ControlsError::Conflict(key, *action, *other));
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The key called `name`, in any case
+fn parse_key(name: &str) -> Option<Key> {
+    KEYS.iter()
+        .cloned()
+        .find(|key| format!("{:?}", key).eq_ignore_ascii_case(name))
+}
+
+impl Default for Controls {
+    fn default() -> Self {
+        Self {
+            bindings: Action::ALL
+                .iter()
+                .map(|&action| (action, action.default_keys()))
+                .collect(),
+        }
+    }
+}
+
+impl fmt::Display for Action {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let description = match self {
+            Action::MoveLeft => "Move left",
+            Action::MoveRight => "Move right",
+            Action::MoveUp => "Move up",
+            Action::MoveDown => "Move down",
+            Action::DepthUp => "Move up the depth axis",
+            Action::DepthDown => "Move down the depth axis",
+            Action::RotateForward => "Turn forward",
+            Action::RotateBack => "Turn back",
+            Action::Undo => "Undo",
+            Action::Redo => "Redo",
+            Action::Hint => "Hint",
+            Action::Pause => "Pause",
+            Action::Scores => "High scores",
+            Action::Save => "Save",
+            Action::Rebind => "Controls",
+        };
+        write!(f, "{}", description)
+    }
+}
+
+impl fmt::Display for ControlsError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ControlsError::NotControls => write!(f, "This is not a controls file"),
+            ControlsError::Malformed(line) => {
+                write!(f, "Line {} should look like `action = Key, Key`", line)
+            }
+            ControlsError::UnknownAction(line, name) => {
+                write!(f, "Line {}: there is no action called {}", line, name)
+            }
+            ControlsError::UnknownKey(line, name) => {
+                write!(f, "Line {}: there is no key called {}", line, name)
+            }
+            ControlsError::Conflict(key, first, second) => {
+                write!(f, "{:?} is used for both {} and {}", key, first, second)
+            }
+        }
+    }
+}
+
+impl Error for ControlsError {}
+
+/// The screen where the keys are changed. One action is picked with Up and
+/// Down, then Return waits for the key to move it onto and Tab waits for an
+/// alternate key. These keys are fixed so the screen always works however
+/// the controls are set up
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Rebinding {
+    selected: usize,
+    /// Whether the next key is being waited for, and if it is an alternate
+    waiting: Option<bool>,
+    message: Option<String>,
+}
+
+impl Rebinding {
+    /// Handles a key press on the screen. Returns false once the screen is
+    /// closed with Escape
+    pub fn key(&mut self, controls: &mut Controls, key: Key) -> bool {
+        let action = self.selected();
+        if let Some(alternate) = self.waiting.take() {
+            if key == Key::Escape {
+                self.message = None;
+                return true;
+            }
+
+            let result = if alternate {
+                controls.add(action, key)
+            } else {
+                controls.bind(action, key)
+            };
+            self.message = result.err().map(|error| error.to_string());
+            return true;
+        }
+
+        self.message = None;
+        match key {
+            Key::Up => {
+                self.selected = (self.selected + Action::ALL.len() - 1) % Action::ALL.len();
+            }
+            Key::Down => self.selected = (self.selected + 1) % Action::ALL.len(),
+            Key::Return => self.waiting = Some(false),
+            Key::Tab => self.waiting = Some(true),
+            Key::Escape => return false,
+            _ => {}
+        }
+        true
+    }
+
+    /// The action being changed
+    pub fn selected(&self) -> Action {
+        Action::ALL[self.selected]
+    }
+
+    /// What the player should do next, or why their last key didn't work
+    pub fn message(&self) -> String {
+        match (&self.message, self.waiting) {
+            (Some(message), _) => message.clone(),
+            (None, Some(false)) => format!("Press the new key for {}", self.selected()),
+            (None, Some(true)) => format!("Press another key for {}", self.selected()),
+            (None, None) => "Return: change  Tab: add  Esc: done".to_string(),
+        }
+    }
+}
+
+/// The keyboard of a frontend. Keys are turned into actions with `controls`
+/// and `Action::Rebind` opens the rebinding screen, which takes every key
+/// until it is closed
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Keyboard {
+    controls: Controls,
+    rebinding: Option<Rebinding>,
+    /// Where `controls` is saved when the rebinding screen is closed. They
+    /// are only kept in memory if there is no path
+    path: Option<PathBuf>,
+}
+
+impl Keyboard {
+    /// A keyboard using `controls`, which are never saved
+    pub fn new(controls: Controls) -> Self {
+        Self {
+            controls,
+            rebinding: None,
+            path: None,
+        }
+    }
+
+    /// A keyboard using the controls in `path`, which are saved back there
+    /// when they are changed
+    pub fn load<P: AsRef<Path>>(path: P) -> Self {
+        Self {
+            path: Some(path.as_ref().to_path_buf()),
+            ..Self::new(Controls::load(path))
+        }
+    }
+
+    /// The action `key` does, if the rebinding screen didn't take it
+    pub fn key_press(&mut self, key: Key) -> Option<Action> {
+        if let Some(rebinding) = &mut self.rebinding {
+            if !rebinding.key(&mut self.controls, key) {
+                self.rebinding = None;
+                if let Some(path) = &self.path {
+                    if let Err(error) = self.controls.save(path) {
+                        eprintln!("Could not save the controls: {}", error);
+                    }
+                }
+            }
+            return None;
+        }
+
+        match self.controls.action(key) {
+            Some(Action::Rebind) => {
+                self.rebinding = Some(Rebinding::default());
+                None
+            }
+            action => action,
+        }
+    }
+
+    /// Whether the rebinding screen is open. Only the keyboard works on it,
+    /// so frontends drop any other input while it is
+    pub fn is_rebinding(&self) -> bool {
+        self.rebinding.is_some()
+    }
+
+    pub fn controls(&self) -> &Controls {
+        &self.controls
+    }
+
+    /// The rebinding screen, with the keys of every action and the one being
+    /// changed marked, if it is open. It covers everything else
+    #[allow(clippy::cast_possible_truncation)]
+    pub fn overlay(&self) -> Option<Vec<(String, [f64; 2])>> {
+        let rebinding = self.rebinding.as_ref()?;
+        let mut texts = vec![("Controls".to_string(), [10.0, 50.0])];
+        for (row, &action) in Action::ALL.iter().enumerate() {
+            let keys = self
+                .controls
+                .keys(action)
+                .iter()
+                .map(|key| format!("{:?}", key))
+                .collect::<Vec<_>>();
+            texts.push((
+                format!(
+                    "{} {:<17}{}",
+                    if action == rebinding.selected() {
+                        '>'
+                    } else {
+                        ' '
+                    },
+                    action.to_string(),
+                    keys.join(", ")
+                ),
+                [10.0, 100.0 + f64::from(row as u32) * 45.0],
+            ));
+        }
+        texts.push((rebinding.message(), [10.0, 875.0]));
+        Some(texts)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn defaults_are_valid() {
+        let controls = Controls::default();
+        assert_eq!(controls.validate(), Ok(()));
+        assert_eq!(controls.action(Key::D), Some(Action::RotateForward));
+        assert_eq!(controls.action(Key::Q), None);
+        assert_eq!(controls.key_name(Action::DepthUp), "PageUp");
+        for &action in &Action::ALL {
+            assert_eq!(Action::from_name(action.name()), Some(action));
+        }
+    }
+
+    #[test]
+    fn text_round_trip() {
+        let mut controls = Controls::default();
+        controls.add(Action::MoveUp, Key::W).unwrap();
+        controls.bind(Action::RotateBack, Key::Q).unwrap();
+        assert_eq!(Controls::from_text(&controls.to_text()), Ok(controls));
+    }
+
+    #[test]
+    fn alternates_and_defaults() {
+        let text = "4d-maze-controls 1\n\
+                    # WASD as well as the arrows\n\
+                    move_left = Left, a\n\
+                    move_up = Up, W\n\
+                    rotate_back = Q\n";
+        let controls = Controls::from_text(text).unwrap();
+        assert_eq!(controls.action(Key::A), Some(Action::MoveLeft));
+        assert_eq!(controls.action(Key::W), Some(Action::MoveUp));
+        assert_eq!(controls.action(Key::Up), Some(Action::MoveUp));
+        assert_eq!(controls.keys(Action::RotateBack), &[Key::Q]);
+        assert_eq!(controls.action(Key::H), Some(Action::Hint));
+    }
+
+    #[test]
+    fn conflicts_are_refused() {
+        let text = "4d-maze-controls 1\nundo = U\nredo = U\n";
+        assert_eq!(
+            Controls::from_text(text),
+            Err(ControlsError::Conflict(Key::U, Action::Undo, Action::Redo))
+        );
+        assert_eq!(
+            Controls::from_text("4d-maze-controls 1\nhint = Hyper\n"),
+            Err(ControlsError::UnknownKey(2, "Hyper".to_string()))
+        );
+        assert_eq!(
+            Controls::from_text("4d-maze-controls 1\njump = J\n"),
+            Err(ControlsError::UnknownAction(2, "jump".to_string()))
+        );
+        assert_eq!(
+            Controls::from_text("4d-maze-controls 1\n\nhint\n"),
+            Err(ControlsError::Malformed(3))
+        );
+
+        let mut controls = Controls::default();
+        assert_eq!(
+            controls.add(Action::MoveRight, Key::D),
+            Err(ControlsError::Conflict(
+                Key::D,
+                Action::RotateForward,
+                Action::MoveRight
+            ))
+        );
+        assert_eq!(controls, Controls::default());
+    }
+
+    #[test]
+    fn rebinding_screen() {
+        let mut controls = Controls::default();
+        let mut screen = Rebinding::default();
+        assert!(screen.key(&mut controls, Key::Down));
+        assert_eq!(screen.selected(), Action::MoveRight);
+
+        // Tab then D adds an alternate, which is refused as D turns
+        screen.key(&mut controls, Key::Tab);
+        screen.key(&mut controls, Key::D);
+        assert!(screen.message().contains("both"));
+        assert_eq!(controls.keys(Action::MoveRight), &[Key::Right]);
+
+        // Return then E replaces the binding
+        screen.key(&mut controls, Key::Return);
+        screen.key(&mut controls, Key::E);
+        assert_eq!(controls.keys(Action::MoveRight), &[Key::E]);
+
+        // Escape while waiting cancels, and closes the screen otherwise
+        screen.key(&mut controls, Key::Return);
+        assert!(screen.key(&mut controls, Key::Escape));
+        assert_eq!(controls.keys(Action::MoveRight), &[Key::E]);
+        assert!(!screen.key(&mut controls, Key::Escape));
+    }
+
+    #[test]
+    fn keyboard() {
+        let mut keyboard = Keyboard::default();
+        assert_eq!(keyboard.key_press(Key::Up), Some(Action::MoveUp));
+        assert_eq!(keyboard.overlay(), None);
+
+        // The rebinding screen takes every key until Escape closes it
+        assert_eq!(keyboard.key_press(Key::K), None);
+        assert!(keyboard.is_rebinding());
+        assert_eq!(keyboard.key_press(Key::Up), None);
+        let overlay = keyboard.overlay().unwrap();
+        assert!(overlay
+            .iter()
+            .any(|(text, _)| text.starts_with("> Controls")));
+        assert_eq!(keyboard.key_press(Key::Escape), None);
+        assert!(!keyboard.is_rebinding());
+        assert_eq!(keyboard.key_press(Key::Up), Some(Action::MoveUp));
+    }
+}
@@ -0,0 +1,767 @@
+//! A frontend built on an entity component system. I first tried to write
+//! the game with Amethyst, which never compiled on my computers, so this uses
+//! specs (the ECS Amethyst is built on) and draws in a piston window instead.
+//!
+//! Every cell of the slice is an entity with a `Position` and either a `Wall`
+//! or a `Room`, and the player, the hint and the depth cues are entities too.
+//! Systems hand the input to the `GameState`, which works out the moves,
+//! start the next maze when one is solved and show the banner for it, then
+//! keep the entities in step with the game. How the window looks is set in
+//! `DEFAULT_DISPLAY_FILE`
+use super::{
+    controls::{Action, Keyboard, DEFAULT_CONTROLS_FILE},
+    game_state::GameState,
+    gamepad::Gamepad,
+    maze_controller::{cell_colour, colours, cue_triangle, draw_lines},
+    mouse::{Mouse, MouseInput},
+    window::{self, WindowFrontend},
+};
+use crate::{Direction, MazeAxis4, MazeCell};
+use graphics::{
+    character::CharacterCache, types::Color as Colour, Context, Ellipse, Graphics, Polygon,
+    Rectangle,
+};
+use opengl_graphics::{GlGraphics, GlyphCache};
+use piston::{
+    input::{Button, Event, Key},
+    window::WindowSettings,
+};
+use serde_derive::Deserialize;
+use specs::{
+    Builder, Component, Dispatcher, DispatcherBuilder, Entities, Join, NullStorage, Read,
+    ReadExpect, ReadStorage, System, VecStorage, World, WorldExt, Write, WriteExpect, WriteStorage,
+};
+use std::{fs, io, path::Path};
+
+/// The file the look of the window is loaded from
+pub const DEFAULT_DISPLAY_FILE: &str = "resources/display_config.ron";
+
+/// Seconds the message saying a maze was solved stays up
+pub const BANNER_TIME: f64 = 2.0;
+
+/// How the window looks. Anything left out of the display file keeps its
+/// default
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct DisplayConfig {
+    /// The title of the window
+    pub title: String,
+    /// The width and height of the window in pixels
+    pub dimensions: (u32, u32),
+    pub fullscreen: bool,
+    pub vsync: bool,
+    /// The colour behind the maze
+    pub background: Colour,
+    /// The biggest a cell is drawn, in pixels
+    pub cell_size: f64,
+    /// The space kept free above and to the left of the slice, in pixels
+    pub margin: f64,
+    /// How much of its cell a wall fills across, from 0 to 1. Thinner walls
+    /// leave gaps that show which way each wall runs
+    pub wall_thickness: f64,
+}
+
+/// Where an entity is on the slice
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Position {
+    pub x: usize,
+    pub y: usize,
+}
+
+/// A wall of the slice
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Wall {
+    pub orient: Orientation,
+    pub colour: Colour,
+}
+
+/// Which way a wall runs. Rooms are at odd co-ordinates, so a wall with an
+/// even x and an odd y sits between two rooms side by side and runs
+/// vertically. Walls where both are even are the posts in the corners
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Orientation {
+    Horizontal,
+    Vertical,
+    Post,
+}
+
+/// A cell of the slice that can be walked through
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Room {
+    pub colour: Colour,
+}
+
+/// The player. There is only ever one
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Player;
+
+/// A cell of the hint being shown
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct HintMark;
+
+/// An opening along a hidden axis. `depth` is whether it is along the depth
+/// axis
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct DepthCue {
+    pub dir: Direction,
+    pub depth: bool,
+}
+
+/// Something the player did, waiting for the next update
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Command {
+    Key(Key),
+    Act(Action),
+    /// A click at a point on the screen
+    Click([f64; 2]),
+    Focus(bool),
+}
+
+/// The commands since the last update
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Commands(pub Vec<Command>);
+
+/// Seconds since the last update
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct DeltaTime(pub f64);
+
+/// Where the slice is drawn on the screen
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Layout {
+    /// The size of one cell in pixels
+    pub scale: f64,
+    /// The top left corner of the slice
+    pub offset: [f64; 2],
+    /// The number of cells along each side of the slice
+    pub dim: (usize, usize),
+}
+
+/// The message saying a maze was solved. `level` is the level the message
+/// was last shown for
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Banner {
+    pub level: usize,
+    pub time_left: f64,
+}
+
+/// Turns the keys into actions with the `Keyboard`, hands the commands to the
+/// `GameState` and moves its clocks on
+pub struct InputSystem;
+
+/// Counts down the banner
+pub struct BannerSystem;
+
+/// Starts the next maze once the player reaches the exit, and puts up the
+/// banner saying the maze was solved
+pub struct WinSystem;
+
+/// Makes the wall and room entities again whenever the slice changes, which
+/// happens when the player turns, moves along a hidden axis or starts a new
+/// maze
+#[derive(Default)]
+pub struct SliceSystem {
+    shown: Option<(usize, MazeAxis4, (usize, usize))>,
+}
+
+/// Moves the player entity and makes the hint and depth cue entities again
+pub struct MarkerSystem;
+
+impl<'a> System<'a> for InputSystem {
+    type SystemData = (
+        WriteExpect<'a, GameState>,
+        WriteExpect<'a, Keyboard>,
+        Write<'a, Commands>,
+        Read<'a, DeltaTime>,
+        Read<'a, Layout>,
+    );
+
+    fn run(&mut self, (mut state, mut keyboard, mut commands, dt, layout): Self::SystemData) {
+        for command in commands.0.drain(..) {
+            // Only the keyboard works on the rebinding screen
+            match command {
+                Command::Key(key) => {
+                    if let Some(action) = keyboard.key_press(key) {
+                        state.apply(action);
+                    }
+                }
+                Command::Focus(focused) => state.set_focus(focused),
+                _ if keyboard.is_rebinding() => {}
+                Command::Act(action) => state.apply(action),
+                Command::Click(point) => {
+                    if let Some(cell) = layout.cell_at(point) {
+                        state.walk_to(cell);
+                    }
+                }
+            }
+        }
+        state.update(dt.0);
+    }
+}
+
+impl<'a> System<'a> for BannerSystem {
+    type SystemData = (Read<'a, DeltaTime>, Write<'a, Banner>);
+
+    fn run(&mut self, (dt, mut banner): Self::SystemData) {
+        banner.time_left = (banner.time_left - dt.0).max(0.0);
+    }
+}
+
+impl<'a> System<'a> for WinSystem {
+    type SystemData = (WriteExpect<'a, GameState>, Write<'a, Banner>);
+
+    fn run(&mut self, (mut state, mut banner): Self::SystemData) {
+        if state.is_solved() {
+            state.next_level();
+            *banner = Banner {
+                level: state.level(),
+                time_left: BANNER_TIME,
+            };
+        }
+    }
+}
+
+impl<'a> System<'a> for SliceSystem {
+    #[allow(clippy::type_complexity)]
+    type SystemData = (
+        Entities<'a>,
+        ReadExpect<'a, GameState>,
+        ReadExpect<'a, DisplayConfig>,
+        Write<'a, Layout>,
+        WriteStorage<'a, Position>,
+        WriteStorage<'a, Wall>,
+        WriteStorage<'a, Room>,
+    );
+
+    fn run(
+        &mut self,
+        (entities, state, config, mut layout, mut positions, mut walls, mut rooms): Self::SystemData,
+    ) {
+        let plane = state.plane();
+        let (first, second) = plane.hidden_axes();
+        let pos = state.player_pos();
+        let pos = [pos.0, pos.1, pos.2, pos.3];
+        let shown = (state.level(), plane, (pos[first], pos[second]));
+        if self.shown == Some(shown) {
+            return;
+        }
+        self.shown = Some(shown);
+
+        for (entity, _) in (&*entities, &walls).join() {
+            entities.delete(entity).expect("Joined entities are alive");
+        }
+        for (entity, _) in (&*entities, &rooms).join() {
+            entities.delete(entity).expect("Joined entities are alive");
+        }
+
+        let slice = state.create_slice();
+        *layout = Layout::new(&config, slice.0.dim());
+        for ((x, y), cell) in slice.0.indexed_iter() {
+            let builder = entities
+                .build_entity()
+                .with(Position { x, y }, &mut positions);
+            let colour = cell_colour(cell);
+            match cell {
+                MazeCell::Wall | MazeCell::OuterWall => builder
+                    .with(
+                        Wall {
+                            orient: Orientation::at(x, y),
+                            colour,
+                        },
+                        &mut walls,
+                    )
+                    .build(),
+                _ => builder.with(Room { colour }, &mut rooms).build(),
+            };
+        }
+    }
+}
+
+impl<'a> System<'a> for MarkerSystem {
+    type SystemData = (
+        Entities<'a>,
+        ReadExpect<'a, GameState>,
+        ReadExpect<'a, Keyboard>,
+        WriteStorage<'a, Position>,
+        ReadStorage<'a, Player>,
+        WriteStorage<'a, HintMark>,
+        WriteStorage<'a, DepthCue>,
+    );
+
+    #[allow(clippy::type_complexity)]
+    fn run(
+        &mut self,
+        (entities, state, keyboard, mut positions, players, mut hint_marks, mut cues): Self::SystemData,
+    ) {
+        let view = state.view(keyboard.controls());
+        let (x, y) = view.player;
+        for (position, _) in (&mut positions, &players).join() {
+            *position = Position { x, y };
+        }
+
+        for (entity, _) in (&*entities, &hint_marks).join() {
+            entities.delete(entity).expect("Joined entities are alive");
+        }
+        for (entity, _) in (&*entities, &cues).join() {
+            entities.delete(entity).expect("Joined entities are alive");
+        }
+
+        for &(x, y) in &view.hint {
+            entities
+                .build_entity()
+                .with(Position { x, y }, &mut positions)
+                .with(HintMark, &mut hint_marks)
+                .build();
+        }
+        let (first, _) = view.plane.hidden_axes();
+        for &((x, y), dir) in &view.depth_cues {
+            entities
+                .build_entity()
+                .with(Position { x, y }, &mut positions)
+                .with(
+                    DepthCue {
+                        dir,
+                        depth: dir.axis == first,
+                    },
+                    &mut cues,
+                )
+                .build();
+        }
+    }
+}
+
+impl Component for Position {
+    type Storage = VecStorage<Self>;
+}
+
+impl Component for Wall {
+    type Storage = VecStorage<Self>;
+}
+
+impl Component for Room {
+    type Storage = VecStorage<Self>;
+}
+
+impl Component for Player {
+    type Storage = NullStorage<Self>;
+}
+
+impl Component for HintMark {
+    type Storage = NullStorage<Self>;
+}
+
+impl Component for DepthCue {
+    type Storage = VecStorage<Self>;
+}
+
+impl Orientation {
+    /// The way the wall at `x`, `y` on the slice runs
+    pub fn at(x: usize, y: usize) -> Self {
+        match (x % 2 == 0, y % 2 == 0) {
+            (true, true) => Orientation::Post,
+            (true, false) => Orientation::Vertical,
+            _ => Orientation::Horizontal,
+        }
+    }
+}
+
+impl Wall {
+    /// The part of the cell at `rect` this wall fills
+    pub fn rect(&self, rect: [f64; 4], thickness: f64) -> [f64; 4] {
+        let [x, y, width, height] = rect;
+        let (thin_x, thin_y) = (width * thickness, height * thickness);
+        let (inset_x, inset_y) = ((width - thin_x) / 2.0, (height - thin_y) / 2.0);
+        match self.orient {
+            Orientation::Horizontal => [x, y + inset_y, width, thin_y],
+            Orientation::Vertical => [x + inset_x, y, thin_x, height],
+            Orientation::Post => [x + inset_x, y + inset_y, thin_x, thin_y],
+        }
+    }
+}
+
+impl Layout {
+    /// Fits a slice `dim` cells big into the window. Cells are never drawn
+    /// bigger than `cell_size`, and the slice keeps the same margin on every
+    /// side of the shorter side of the window
+    #[allow(clippy::cast_possible_truncation)]
+    pub fn new(config: &DisplayConfig, dim: (usize, usize)) -> Self {
+        let (width, height) = config.dimensions;
+        let area = f64::from(width.min(height)) - 2.0 * config.margin;
+        let longest = f64::from(dim.0.max(dim.1).max(1) as u32);
+        Self {
+            scale: config.cell_size.min(area / longest).max(1.0),
+            offset: [config.margin; 2],
+            dim,
+        }
+    }
+
+    /// Where the cell at `position` is drawn, as `[x, y, width, height]`
+    #[allow(clippy::cast_possible_truncation)]
+    pub fn rect(&self, position: Position) -> [f64; 4] {
+        [
+            f64::from(position.x as u32) * self.scale + self.offset[0],
+            f64::from(position.y as u32) * self.scale + self.offset[1],
+            self.scale,
+            self.scale,
+        ]
+    }
+
+    /// The cell drawn at `point` on the screen, if there is one
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    pub fn cell_at(&self, point: [f64; 2]) -> Option<(usize, usize)> {
+        if self.scale <= 0.0 {
+            return None;
+        }
+        let x = ((point[0] - self.offset[0]) / self.scale).floor();
+        let y = ((point[1] - self.offset[1]) / self.scale).floor();
+        if x < 0.0 || y < 0.0 {
+            return None;
+        }
+
+        let (x, y) = (x as usize, y as usize);
+        if x < self.dim.0 && y < self.dim.1 {
+            Some((x, y))
+        } else {
+            None
+        }
+    }
+}
+
+impl DisplayConfig {
+    /// Loads the display file at `path`. The defaults are used if there is
+    /// no file, and if it can't be read the error is printed and the
+    /// defaults are used
+    pub fn load<P: AsRef<Path>>(path: P) -> Self {
+        let path = path.as_ref();
+        let text = match fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(ref error) if error.kind() == io::ErrorKind::NotFound => return Self::default(),
+            Err(error) => {
+                eprintln!("Could not read the display config: {}", error);
+                return Self::default();
+            }
+        };
+
+        Self::from_text(&text).unwrap_or_else(|error| {
+            eprintln!(
+                "The display config in {} is broken, the defaults are used instead: {}",
+                path.display(),
+                error
+            );
+            Self::default()
+        })
+    }
+
+    /// Reads a display config written in RON. A wall thickness outside 0 to
+    /// 1 is moved to the nearest end
+    pub fn from_text(text: &str) -> Result<Self, ron::de::Error> {
+        let mut config: Self = ron::de::from_str(text)?;
+        config.wall_thickness = config.wall_thickness.max(0.0).min(1.0);
+        Ok(config)
+    }
+}
+
+impl Default for DisplayConfig {
+    fn default() -> Self {
+        Self {
+            title: "4D maze".to_string(),
+            dimensions: (900, 900),
+            fullscreen: false,
+            vsync: false,
+            background: [1.0; 4],
+            cell_size: 65.0,
+            margin: 130.0,
+            wall_thickness: 1.0,
+        }
+    }
+}
+
+/// Makes the world for `state` played with `keyboard`, with the player
+/// entity in it, and the dispatcher that runs the systems on it each update.
+/// `WinSystem` starts the next maze instead of `state`
+pub fn new_world(
+    mut state: GameState,
+    keyboard: Keyboard,
+    config: DisplayConfig,
+) -> (World, Dispatcher<'static, 'static>) {
+    let mut world = World::new();
+    let mut dispatcher = DispatcherBuilder::new()
+        .with(InputSystem, "input", &[])
+        .with(BannerSystem, "banner", &["input"])
+        .with(WinSystem, "win", &["banner"])
+        .with(SliceSystem::default(), "slice", &["win"])
+        .with(MarkerSystem, "markers", &["slice"])
+        .build();
+
+    state.set_auto_advance(false);
+    world.insert(Banner {
+        level: state.level(),
+        time_left: 0.0,
+    });
+    world.insert(state);
+    world.insert(keyboard);
+    world.insert(config);
+    dispatcher.setup(&mut world);
+    world
+        .create_entity()
+        .with(Position::default())
+        .with(Player)
+        .build();
+
+    (world, dispatcher)
+}
+
+/// Runs the systems once, `dt` seconds after they last ran
+pub fn step(world: &mut World, dispatcher: &mut Dispatcher, dt: f64) {
+    world.write_resource::<DeltaTime>().0 = dt;
+    dispatcher.dispatch(world);
+    world.maintain();
+}
+
+pub fn run(state: GameState) {
+    let config = DisplayConfig::load(DEFAULT_DISPLAY_FILE);
+    let (width, height) = config.dimensions;
+    let settings = WindowSettings::new(config.title.clone(), [width, height])
+        .fullscreen(config.fullscreen)
+        .vsync(config.vsync);
+    let background = config.background;
+    let keyboard = Keyboard::load(DEFAULT_CONTROLS_FILE);
+    let (world, dispatcher) = new_world(state, keyboard, config);
+
+    window::run(
+        &settings,
+        background,
+        &mut EcsFrontend {
+            world,
+            dispatcher,
+            gamepad: Gamepad::default(),
+            mouse: Mouse::default(),
+        },
+    );
+}
+
+/// The world being played in the window, with the input devices that aren't
+/// part of it
+struct EcsFrontend {
+    world: World,
+    dispatcher: Dispatcher<'static, 'static>,
+    gamepad: Gamepad,
+    mouse: Mouse,
+}
+
+impl WindowFrontend for EcsFrontend {
+    fn event(&mut self, e: &Event) {
+        queue_commands(
+            &mut self.world.write_resource::<Commands>(),
+            &mut self.gamepad,
+            &mut self.mouse,
+            e,
+        );
+    }
+
+    fn update(&mut self, dt: f64) {
+        let repeats = self.gamepad.update(dt);
+        self.world
+            .write_resource::<Commands>()
+            .0
+            .extend(repeats.into_iter().map(Command::Act));
+        step(&mut self.world, &mut self.dispatcher, dt);
+    }
+
+    fn quit(&mut self) -> Vec<String> {
+        self.world.write_resource::<GameState>().quit()
+    }
+
+    fn draw(&self, glyphs: &mut GlyphCache, c: &Context, g: &mut GlGraphics) {
+        draw(&self.world, glyphs, c, g);
+    }
+}
+
+/// Turns the keys, buttons and clicks of `e` into commands
+fn queue_commands<E: piston::input::GenericEvent>(
+    commands: &mut Commands,
+    gamepad: &mut Gamepad,
+    mouse: &mut Mouse,
+    e: &E,
+) {
+    if let Some(focused) = e.focus_args() {
+        commands.0.push(Command::Focus(focused));
+    }
+    if let Some(Button::Keyboard(key)) = e.press_args() {
+        commands.0.push(Command::Key(key));
+    }
+    if let Some(action) = gamepad.event(e) {
+        commands.0.push(Command::Act(action));
+    }
+    match mouse.event(e) {
+        Some(MouseInput::Click(point)) => commands.0.push(Command::Click(point)),
+        Some(MouseInput::Act(action)) => commands.0.push(Command::Act(action)),
+        None => {}
+    }
+}
+
+/// Draws every entity, then the HUD and the banner. The leaderboard and the
+/// rebinding screen cover everything else
+pub fn draw<G: Graphics, C>(world: &World, glyphs: &mut C, c: &Context, g: &mut G)
+where
+    C: CharacterCache<Texture = G::Texture>,
+{
+    let state = world.read_resource::<GameState>();
+    let keyboard = world.read_resource::<Keyboard>();
+    let view = state.view(keyboard.controls());
+    if let Some(texts) = keyboard.overlay().or_else(|| view.overlay.clone()) {
+        draw_lines(&texts, glyphs, c, g);
+        return;
+    }
+
+    let layout = *world.read_resource::<Layout>();
+    let thickness = world.read_resource::<DisplayConfig>().wall_thickness;
+    let positions = world.read_storage::<Position>();
+    let rooms = world.read_storage::<Room>();
+    let walls = world.read_storage::<Wall>();
+    let hint_marks = world.read_storage::<HintMark>();
+    let cues = world.read_storage::<DepthCue>();
+    let players = world.read_storage::<Player>();
+
+    for (&position, room) in (&positions, &rooms).join() {
+        Rectangle::new(room.colour).draw(layout.rect(position), &c.draw_state, c.transform, g);
+    }
+    for (&position, wall) in (&positions, &walls).join() {
+        Rectangle::new(wall.colour).draw(
+            wall.rect(layout.rect(position), thickness),
+            &c.draw_state,
+            c.transform,
+            g,
+        );
+    }
+    for (&position, _) in (&positions, &hint_marks).join() {
+        let [x, y, width, height] = layout.rect(position);
+        Rectangle::new(colours::ORANGE).draw(
+            [x + width / 4.0, y + height / 4.0, width / 2.0, height / 2.0],
+            &c.draw_state,
+            c.transform,
+            g,
+        );
+    }
+    for (&position, cue) in (&positions, &cues).join() {
+        let (triangle, colour) = cue_triangle(layout.rect(position), cue.depth, cue.dir.positive);
+        Polygon::new(colour).draw(&triangle, &c.draw_state, c.transform, g);
+    }
+    for (&position, _) in (&positions, &players).join() {
+        Ellipse::new(colours::YELLOW).draw(layout.rect(position), &c.draw_state, c.transform, g);
+    }
+
+    draw_lines(&view.hud, glyphs, c, g);
+    let banner = world.read_resource::<Banner>();
+    if banner.time_left > 0.0 {
+        let text = format!("Maze {} solved!", banner.level);
+        draw_lines(&[(text, [560.0, 25.0])], glyphs, c, g);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A world playing a straight corridor along X, with the exit 4 steps
+    /// from the player
+    fn corridor() -> (World, Dispatcher<'static, 'static>) {
+        new_world(
+            GameState::corridor(),
+            Keyboard::default(),
+            DisplayConfig::default(),
+        )
+    }
+
+    fn count<T: Component>(world: &World) -> usize {
+        world.read_storage::<T>().join().count()
+    }
+
+    fn player(world: &World) -> Position {
+        let positions = world.read_storage::<Position>();
+        let players = world.read_storage::<Player>();
+        let (&position, _) = (&positions, &players).join().next().unwrap();
+        position
+    }
+
+    fn act(world: &mut World, dispatcher: &mut Dispatcher, action: Action) {
+        world
+            .write_resource::<Commands>()
+            .0
+            .push(Command::Act(action));
+        step(world, dispatcher, 0.0);
+    }
+
+    #[test]
+    fn display_config() {
+        let config = DisplayConfig::from_text(
+            "(title: \"Maze\", dimensions: (600, 400), wall_thickness: 3.0)",
+        )
+        .unwrap();
+        assert_eq!(config.title, "Maze");
+        assert_eq!(config.dimensions, (600, 400));
+        assert_eq!(config.wall_thickness, 1.0);
+        assert_eq!(config.margin, DisplayConfig::default().margin);
+        assert!(DisplayConfig::from_text("(dimensions: 600)").is_err());
+
+        // The file in the repository is the same as the defaults
+        let file = DisplayConfig::from_text(include_str!("../../resources/display_config.ron"));
+        assert_eq!(file.unwrap(), DisplayConfig::default());
+    }
+
+    #[test]
+    fn walls_and_layout() {
+        assert_eq!(Orientation::at(2, 1), Orientation::Vertical);
+        assert_eq!(Orientation::at(1, 2), Orientation::Horizontal);
+        assert_eq!(Orientation::at(0, 0), Orientation::Post);
+        let wall = Wall {
+            orient: Orientation::Vertical,
+            colour: colours::BLUE,
+        };
+        assert_eq!(
+            wall.rect([10.0, 20.0, 8.0, 8.0], 0.5),
+            [12.0, 20.0, 4.0, 8.0]
+        );
+
+        let layout = Layout::new(&DisplayConfig::default(), (20, 5));
+        assert_eq!(layout.scale, 32.0);
+        assert_eq!(layout.cell_at([130.0 + 32.0 * 3.5, 131.0]), Some((3, 0)));
+        assert_eq!(layout.cell_at([129.0, 140.0]), None);
+        assert_eq!(layout.cell_at([140.0, 130.0 + 32.0 * 5.0]), None);
+    }
+
+    #[test]
+    fn entities_follow_the_game() {
+        let (mut world, mut dispatcher) = corridor();
+        step(&mut world, &mut dispatcher, 0.0);
+        assert_eq!(count::<Room>(&world), 5);
+        assert_eq!(count::<Wall>(&world), 7 * 3 - 5);
+        assert_eq!(player(&world), Position { x: 1, y: 1 });
+
+        act(&mut world, &mut dispatcher, Action::MoveRight);
+        assert_eq!(player(&world), Position { x: 2, y: 1 });
+        act(&mut world, &mut dispatcher, Action::Hint);
+        assert_eq!(count::<HintMark>(&world), 3);
+        act(&mut world, &mut dispatcher, Action::MoveRight);
+        assert_eq!(count::<HintMark>(&world), 0);
+    }
+
+    #[test]
+    fn solving_a_maze() {
+        let (mut world, mut dispatcher) = corridor();
+        for _ in 0..4 {
+            act(&mut world, &mut dispatcher, Action::MoveRight);
+        }
+        assert_eq!(
+            *world.read_resource::<Banner>(),
+            Banner {
+                level: 1,
+                time_left: BANNER_TIME
+            }
+        );
+        // The next maze is 7 by 7 on every plane
+        assert_eq!(count::<Room>(&world) + count::<Wall>(&world), 49);
+
+        step(&mut world, &mut dispatcher, BANNER_TIME);
+        assert_eq!(world.read_resource::<Banner>().time_left, 0.0);
+    }
+}
@@ -0,0 +1,978 @@
+//! The game itself, apart from how it is shown. `GameState` is changed only
+//! by `apply`, `walk_to` and `update`, and `view` gives everything that
+//! should be on the screen. The window, the terminal and the tests are all
+//! frontends that turn their input into `Action`s and draw the `View`
+use super::{
+    controls::{Action, Controls},
+    high_scores::{HighScores, ScoreEntry},
+    hint::{Hint, HINT_COST, HINT_LENGTH},
+    history::History,
+    maze_controller::GameSettings,
+    save::SaveGame,
+    timer::{format_time, GameClock},
+};
+use crate::{
+    par, slice_path, try_move, Direction, Maze, MazeAxis4, MazeCell, MazeFileError, MazeMode,
+    MazePos, MazeSlice,
+};
+use rand::random;
+use std::collections::VecDeque;
+
+/// The name of each axis by its index
+const AXIS_NAMES: [char; 4] = ['X', 'Y', 'Z', 'W'];
+
+/// The points a maze is worth when it is finished in par
+const PAR_POINTS: u64 = 100;
+
+/// Seconds between the steps of a walk to a clicked cell
+pub const WALK_INTERVAL: f64 = 0.08;
+
+/// `BaseData` is a struct that contains data that is meant to be stored for a
+/// long time (i.e. the whole game). It contains 14 fields:
+///
+/// * `current_maze` which holds the current maze
+/// * `start_pos` holds the position of `MazeCell::Start`
+/// * `end_pos` holds the position of `MazeCell::End`
+/// * `settings` which holds the `GameSettings` the game was started with
+/// * `seed` which holds the seed `current_maze` was generated from
+/// * `first_seed` which holds the seed of the first maze of the game
+/// * `level` which holds how many mazes have been finished
+/// * `score` which contains the user's score
+/// * `moves` which holds how many steps and turns the player has taken
+/// * `level_moves` which holds how many of `moves` were on this maze
+/// * `undos` which holds how many moves have been undone
+/// * `level_hints` which holds how many hints were used on this maze
+/// * `par` which holds the fewest steps and turns this maze can be solved in
+/// * `clock` which holds the level and session timers
+pub struct BaseData {
+    current_maze: Maze,
+    start_pos: MazePos,
+    end_pos: MazePos,
+    settings: GameSettings,
+    seed: u64,
+    first_seed: u64,
+    level: usize,
+    score: u64,
+    moves: u64,
+    level_moves: u64,
+    undos: u64,
+    level_hints: u64,
+    par: usize,
+    clock: GameClock,
+}
+
+/// `GameState` is a struct that contains data that is meant to be stored for
+/// a short time (i.e. the current level). It contains 11 fields:
+///
+/// * `player_pos` which holds the player's `player_pos`
+/// * `cut_axis` which contains the plane the data is cut through
+/// * `base_data` which contains the `BaseData` for the game
+/// * `high_scores` which holds the high-score table
+/// * `show_scores` which holds whether the leaderboard is shown instead of
+/// the maze
+/// * `history` which holds the positions and planes that can be undone
+/// * `hint` which holds the hint being shown, if there is one
+/// * `walk` which holds the steps left of a walk to a clicked cell
+/// * `walk_wait` which holds the seconds until the next step of `walk`
+/// * `message` which holds how the last save went, or why the exit is nearer
+/// than the settings asked for, shown on the HUD until the player does
+/// something else
+/// * `auto_advance` which holds whether the next maze starts as soon as the
+/// player reaches the exit. A frontend that turns it off starts it with
+/// `next_level`
+///
+/// `cut_axis` and `player_pos` are used to construct the slice 'on the go'
+pub struct GameState {
+    player_pos: MazePos,
+    cut_axis: MazeAxis4,
+    base_data: BaseData,
+    high_scores: HighScores,
+    show_scores: bool,
+    history: History<(MazePos, MazeAxis4)>,
+    hint: Option<Hint>,
+    walk: VecDeque<Action>,
+    walk_wait: f64,
+    message: Option<String>,
+    auto_advance: bool,
+}
+
+/// Everything a frontend shows, worked out from a `GameState`. Nothing in it
+/// can change the game
+pub struct View<'a> {
+    /// The slice through the player
+    pub slice: MazeSlice<'a>,
+    /// The plane the maze is cut through
+    pub plane: MazeAxis4,
+    /// The cell of the slice the player is in
+    pub player: (usize, usize),
+    /// The cells of the slice the hint marks, if one is being shown
+    pub hint: Vec<(usize, usize)>,
+    /// Every opening along the hidden axes from the traversable cells of the
+    /// slice, with the cell it is in
+    pub depth_cues: Vec<((usize, usize), Direction)>,
+    /// The lines of the HUD and where they go on a 900 by 900 screen
+    pub hud: Vec<(String, [f64; 2])>,
+    /// The screen covering the maze, if the leaderboard is open
+    pub overlay: Option<Vec<(String, [f64; 2])>>,
+}
+
+impl GameState {
+    /// This creates a new `GameState` with a new maze made using `settings`,
+    /// recording its score in `high_scores`
+    pub fn new(settings: GameSettings, high_scores: HighScores) -> Self {
+        let base_data = BaseData::new(settings);
+        let message = base_data.exit_problem();
+        Self {
+            player_pos: base_data.start_pos,
+            cut_axis: MazeAxis4::XY,
+            base_data,
+            high_scores,
+            show_scores: false,
+            history: History::default(),
+            hint: None,
+            walk: VecDeque::new(),
+            walk_wait: 0.0,
+            message,
+            auto_advance: true,
+        }
+    }
+
+    /// Continues a saved game, recording its score in `high_scores`
+    pub fn from_save(save: SaveGame, high_scores: HighScores) -> Self {
+        let mut base_data = BaseData {
+            current_maze: Maze::default(),
+            start_pos: (1, 1, 1, 1),
+            end_pos: (1, 1, 1, 1),
+            settings: save.settings,
+            seed: save.seed,
+            first_seed: save.first_seed,
+            level: save.level,
+            score: save.score,
+            moves: save.moves,
+            level_moves: 0,
+            undos: save.undos,
+            level_hints: 0,
+            par: 0,
+            clock: save.clock,
+        };
+        base_data.start_level(save.maze);
+        base_data.level_moves = save.level_moves;
+        base_data.level_hints = save.level_hints;
+
+        Self {
+            player_pos: save.player_pos,
+            cut_axis: save.cut_axis,
+            base_data,
+            high_scores,
+            show_scores: false,
+            history: History::default(),
+            hint: None,
+            walk: VecDeque::new(),
+            walk_wait: 0.0,
+            message: None,
+            auto_advance: true,
+        }
+    }
+
+    #[cfg(test)]
+    /// A game in `Maze::corridor` with the player on the start and the high
+    /// scores only in memory
+    pub(crate) fn corridor() -> Self {
+        let mut settings = GameSettings::default();
+        settings.shape = (7, 7, 7, 3);
+        Self::from_save(
+            SaveGame {
+                settings,
+                maze: Maze::corridor(),
+                seed: 1,
+                first_seed: 1,
+                level: 0,
+                score: 0,
+                moves: 0,
+                level_moves: 0,
+                undos: 0,
+                level_hints: 0,
+                clock: GameClock::new(None),
+                player_pos: (1, 1, 1, 1),
+                cut_axis: MazeAxis4::XY,
+            },
+            HighScores::default(),
+        )
+    }
+
+    /// Everything a frontend needs to show the game as it is now. The HUD
+    /// tells the player which of `controls` to press
+    pub fn view(&self, controls: &Controls) -> View {
+        let hint = match &self.hint {
+            Some(Hint::Path(path)) => path
+                .iter()
+                .map(|&pos| self.cut_axis.plane_coords(pos))
+                .collect(),
+            _ => Vec::new(),
+        };
+
+        View {
+            slice: self.create_slice(),
+            plane: self.cut_axis,
+            player: self.cut_axis.plane_coords(self.player_pos),
+            hint,
+            depth_cues: self.depth_cues(),
+            hud: self.hud_text(controls),
+            overlay: if self.show_scores {
+                Some(self.scores_text(controls))
+            } else {
+                None
+            },
+        }
+    }
+
+    /// Does what the player asked for, then starts the next maze if the
+    /// player reached the exit (unless `auto_advance` is off). Anything the
+    /// player does stops a walk to a clicked cell and clears the message
+    pub fn apply(&mut self, action: Action) {
+        self.walk.clear();
+        self.message = None;
+        self.step(action);
+    }
+
+    /// Does `action` without stopping a walk
+    fn step(&mut self, action: Action) {
+        self.perform(action);
+        if self.is_solved() {
+            self.walk.clear();
+            if self.auto_advance {
+                self.next_level();
+            }
+        }
+    }
+
+    /// Whether the player is on the exit, so the maze has been solved
+    pub fn is_solved(&self) -> bool {
+        self.base_data.current_maze.0.get(self.player_pos) == Some(&MazeCell::End)
+    }
+
+    /// Sets whether the next maze starts as soon as the player reaches the
+    /// exit. When it is off nothing moves on a solved maze until `next_level`
+    /// is called
+    pub fn set_auto_advance(&mut self, on: bool) {
+        self.auto_advance = on;
+    }
+
+    fn perform(&mut self, action: Action) {
+        // The leaderboard covers the maze so the player can't move
+        if action == Action::Scores {
+            self.show_scores = !self.show_scores;
+            return;
+        } else if self.show_scores {
+            return;
+        }
+
+        match action {
+            Action::Save => {
+                self.message = Some(self.save_message());
+                return;
+            }
+            // The keyboard opens its own rebinding screen
+            Action::Rebind => return,
+            Action::Pause => {
+                self.base_data.clock.toggle_pause();
+                return;
+            }
+            _ => {}
+        }
+
+        // Nothing moves while the game is paused, once the time has run out
+        // or on a solved maze waiting for the next one, but the game can
+        // still be looked at and saved
+        let clock = &self.base_data.clock;
+        if clock.is_paused() || clock.is_out_of_time() || self.is_solved() {
+            return;
+        }
+
+        let state = (self.player_pos, self.cut_axis);
+        if let Some(player_move) = action.player_move() {
+            if let Ok(pos) = try_move(
+                &self.base_data.current_maze,
+                self.player_pos,
+                self.cut_axis,
+                player_move,
+            ) {
+                // Any move makes the hint out of date
+                self.hint = None;
+                self.history.record(state);
+                self.player_pos = pos;
+                self.base_data.count_move();
+            }
+            return;
+        }
+
+        match action {
+            Action::RotateForward | Action::RotateBack => {
+                self.hint = None;
+                self.history.record(state);
+                if action == Action::RotateForward {
+                    self.cut_axis.cycle(self.base_data.settings.mode);
+                } else {
+                    self.cut_axis.cycle_back(self.base_data.settings.mode);
+                }
+                self.base_data.count_move();
+            }
+            Action::Undo => {
+                self.hint = None;
+                if let Some((pos, axis)) = self.history.undo(state) {
+                    self.player_pos = pos;
+                    self.cut_axis = axis;
+                    self.base_data.undos += 1;
+                }
+            }
+            Action::Redo => {
+                self.hint = None;
+                if let Some((pos, axis)) = self.history.redo(state) {
+                    self.player_pos = pos;
+                    self.cut_axis = axis;
+                }
+            }
+            Action::Hint if self.hint.is_none() => {
+                self.hint = Hint::find(
+                    &self.base_data.current_maze,
+                    self.player_pos,
+                    self.cut_axis,
+                    self.base_data.settings.mode,
+                    HINT_LENGTH,
+                );
+                if self.hint.is_some() {
+                    self.base_data.level_hints += 1;
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Walks the player to `cell` of the slice, if it can be reached without
+    /// leaving the slice. The player takes a step every `WALK_INTERVAL`
+    /// seconds so they can see where they went
+    pub fn walk_to(&mut self, cell: (usize, usize)) {
+        if self.show_scores {
+            return;
+        }
+        let to = self.cut_axis.with_plane_coords(self.player_pos, cell);
+        if let Some(moves) = slice_path(
+            &self.base_data.current_maze,
+            self.player_pos,
+            to,
+            self.cut_axis,
+        ) {
+            self.walk = moves.into_iter().map(Action::from).collect();
+            self.walk_wait = 0.0;
+        }
+    }
+
+    /// Moves the clocks on by `dt` seconds and takes the next steps of a
+    /// walk. Frontends call this with the time since they last did. A walk
+    /// waits while the game is paused and carries on afterwards.
+    ///
+    /// When the time runs out in time-attack mode the game is over, so it is
+    /// recorded in the high scores straight away
+    pub fn update(&mut self, dt: f64) {
+        let had_time = !self.base_data.clock.is_out_of_time();
+        self.base_data.clock.tick(dt);
+        if had_time && self.base_data.clock.is_out_of_time() {
+            self.walk.clear();
+            let mut report = vec!["Out of time!".to_string()];
+            report.extend(self.record_score());
+            self.message = Some(report.join(" "));
+            return;
+        }
+        if self.base_data.clock.is_paused() {
+            return;
+        }
+
+        self.walk_wait -= dt;
+        while self.walk_wait <= 0.0 {
+            match self.walk.pop_front() {
+                Some(action) => self.step(action),
+                None => break,
+            }
+            self.walk_wait += WALK_INTERVAL;
+        }
+    }
+
+    /// The clocks stop while the player is in another window
+    pub fn set_focus(&mut self, focused: bool) {
+        self.base_data.clock.set_focus(focused);
+    }
+
+    /// Takes a copy of everything needed to continue this game
+    pub fn to_save(&self) -> SaveGame {
+        SaveGame {
+            settings: self.base_data.settings.clone(),
+            maze: self.base_data.current_maze.clone(),
+            seed: self.base_data.seed,
+            first_seed: self.base_data.first_seed,
+            level: self.base_data.level,
+            score: self.base_data.score,
+            moves: self.base_data.moves,
+            level_moves: self.base_data.level_moves,
+            undos: self.base_data.undos,
+            level_hints: self.base_data.level_hints,
+            clock: self.base_data.clock.clone(),
+            player_pos: self.player_pos,
+            cut_axis: self.cut_axis,
+        }
+    }
+
+    /// Saves the game to the file in its settings
+    pub fn save(&self) -> Result<(), MazeFileError> {
+        self.to_save().save()
+    }
+
+    /// Saves the game and says how it went. A failed save shouldn't stop the
+    /// game so the error is only shown to the player
+    fn save_message(&self) -> String {
+        let path = self.base_data.settings.save_path.display();
+        match self.save() {
+            Ok(()) => format!("Saved the game to {}", path),
+            Err(error) => format!("Could not save the game to {}: {}", path, error),
+        }
+    }
+
+    /// Records the game in the high scores and saves it, ready for the window
+    /// to close. Returns what happened for the frontend to tell the player
+    pub fn quit(&mut self) -> Vec<String> {
+        let mut report = self.record_score();
+        report.push(self.save_message());
+        report
+    }
+
+    /// Records the game in the high scores if it has scored anything.
+    /// Returns what happened for the frontend to tell the player
+    fn record_score(&mut self) -> Vec<String> {
+        let mut report = Vec::new();
+        if self.base_data.score > 0 {
+            let (mode, shape) = self.base_data.settings.board();
+            let entry = ScoreEntry {
+                name: self.base_data.settings.player_name.clone(),
+                score: self.base_data.score,
+                time: self.base_data.clock.session_time(),
+                moves: self.base_data.moves,
+                undos: self.base_data.undos,
+                seed: self.base_data.first_seed,
+                mode,
+                shape,
+            };
+
+            if let Some(place) = self.high_scores.add(entry) {
+                report.push(format!(
+                    "You came number {} on the {} {:?} board",
+                    place + 1,
+                    mode,
+                    shape
+                ));
+            }
+            if let Err(error) = self.high_scores.save(&self.base_data.settings.scores_path) {
+                report.push(format!("Could not save the high scores: {}", error));
+            }
+        }
+        report
+    }
+
+    /// Scores the solved maze and starts the next one
+    pub fn next_level(&mut self) {
+        // `base_data` changes
+        self.base_data.score += level_score(
+            self.base_data.par,
+            self.base_data.level_moves,
+            self.base_data.level_hints,
+        );
+        self.base_data.level += 1;
+        self.base_data.clock.level_finished();
+        self.base_data.seed = random();
+        let maze = self
+            .base_data
+            .settings
+            .level_maze(self.base_data.level, self.base_data.seed);
+        self.base_data.start_level(maze);
+        self.message = self.base_data.exit_problem();
+
+        // Reset `self`
+        self.cut_axis = MazeAxis4::XY;
+        self.player_pos = self.base_data.start_pos;
+        self.history.clear();
+        self.hint = None;
+        self.walk.clear();
+    }
+
+    /// The level being played, counting from 0
+    pub fn level(&self) -> usize {
+        self.base_data.level
+    }
+
+    /// Where the player is in the maze
+    pub fn player_pos(&self) -> MazePos {
+        self.player_pos
+    }
+
+    /// The plane the maze is cut through
+    pub fn plane(&self) -> MazeAxis4 {
+        self.cut_axis
+    }
+
+    pub fn create_slice(&self) -> MazeSlice {
+        self.base_data
+            .current_maze
+            .view_2_axis(self.cut_axis, self.player_pos)
+    }
+
+    /// Every opening along the hidden axes from the traversable cells of the
+    /// slice, with the cell it is in
+    fn depth_cues(&self) -> Vec<((usize, usize), Direction)> {
+        let mut cues = Vec::new();
+        for ((x, y), cell) in self.create_slice().0.indexed_iter() {
+            if cell.is_traversable() {
+                let pos = self.cut_axis.with_plane_coords(self.player_pos, (x, y));
+                for dir in self
+                    .base_data
+                    .current_maze
+                    .hidden_openings(self.cut_axis, pos)
+                {
+                    cues.push(((x, y), dir));
+                }
+            }
+        }
+        cues
+    }
+
+    /// The lines of the HUD and where they go on the screen
+    fn hud_text(&self, controls: &Controls) -> Vec<(String, [f64; 2])> {
+        let current_pos_text = (
+            format!("Current position: {:?}", self.player_pos),
+            [10.0, 25.0],
+        );
+        let start_pos_text = (
+            format!("Start position: {:?}", self.base_data.start_pos),
+            [260.0, 50.0],
+        );
+        let end_pos_text = (
+            format!("End position: {:?}", self.base_data.end_pos),
+            [10.0, 75.0],
+        );
+        let current_axis_text = (
+            format!(
+                "Current Axis: {} ({})",
+                self.cut_axis, self.base_data.settings.mode
+            ),
+            [260.0, 100.0],
+        );
+        let score_text = (format!("Score: {}", self.base_data.score), [10.0, 125.0]);
+        let moves_text = (
+            format!(
+                "Moves: {} (par {}) Undos: {}",
+                self.base_data.level_moves, self.base_data.par, self.base_data.undos
+            ),
+            [10.0, 150.0],
+        );
+        let clock = &self.base_data.clock;
+        let time_text = (
+            format!(
+                "Time: {} / {}{}",
+                format_time(clock.level_time()),
+                format_time(clock.session_time()),
+                if clock.is_paused() { " (paused)" } else { "" }
+            ),
+            [260.0, 150.0],
+        );
+        let time_left_text = (
+            match clock.remaining() {
+                Some(_) if clock.is_out_of_time() => "Out of time!".to_string(),
+                Some(remaining) => format!("Time left: {}", format_time(remaining)),
+                None => String::new(),
+            },
+            [10.0, 175.0],
+        );
+        let message_text = (self.message.clone().unwrap_or_default(), [260.0, 175.0]);
+        let (first, second) = self.cut_axis.hidden_axes();
+        let keys = |action| controls.key_name(action);
+        let depth_keys = format!("{}/{}", keys(Action::DepthUp), keys(Action::DepthDown));
+        // W is always hidden in 3D and there is nothing to see along it
+        let depth_text = (
+            match self.base_data.settings.mode {
+                MazeMode::ThreeD => {
+                    format!("Open along {} ({}): purple", AXIS_NAMES[first], depth_keys)
+                }
+                MazeMode::FourD => format!(
+                    "Open along {} ({}): purple  {}: cyan",
+                    AXIS_NAMES[first], depth_keys, AXIS_NAMES[second]
+                ),
+            },
+            [10.0, 800.0],
+        );
+        let hint_text = (
+            match &self.hint {
+                Some(Hint::Turn {
+                    plane,
+                    turns,
+                    forward,
+                }) => {
+                    let key = keys(if *forward {
+                        Action::RotateForward
+                    } else {
+                        Action::RotateBack
+                    });
+                    match turns {
+                        1 => format!("Hint: press {} to turn onto {}", key, plane),
+                        _ => format!("Hint: press {} {} times to turn onto {}", key, turns, plane),
+                    }
+                }
+                Some(Hint::Path(_)) => "Hint: follow the orange cells".to_string(),
+                Some(Hint::Depth { positive: true }) => {
+                    format!("Hint: press {}", keys(Action::DepthUp))
+                }
+                Some(Hint::Depth { positive: false }) => {
+                    format!("Hint: press {}", keys(Action::DepthDown))
+                }
+                None => format!(
+                    "Press {} for a hint (-{} points)",
+                    keys(Action::Hint),
+                    HINT_COST
+                ),
+            },
+            [10.0, 825.0],
+        );
+        let controls_text = (
+            format!(
+                "{}: pause  {}: controls",
+                keys(Action::Pause),
+                keys(Action::Rebind)
+            ),
+            [10.0, 850.0],
+        );
+        let seed_text = (
+            match self.base_data.settings.pack_level(self.base_data.level) {
+                Some(level) => format!("Level: {}", level.name.as_ref().map_or("", String::as_str)),
+                None => format!(
+                    "Seed: {} ({})",
+                    self.base_data.seed, self.base_data.settings.algorithm
+                ),
+            },
+            [10.0, 875.0],
+        );
+
+        vec![
+            current_pos_text,
+            start_pos_text,
+            end_pos_text,
+            current_axis_text,
+            score_text,
+            moves_text,
+            time_text,
+            time_left_text,
+            message_text,
+            depth_text,
+            hint_text,
+            controls_text,
+            seed_text,
+        ]
+    }
+
+    /// The leaderboard for the size and mode of maze being played
+    #[allow(clippy::cast_possible_truncation)]
+    fn scores_text(&self, controls: &Controls) -> Vec<(String, [f64; 2])> {
+        let (mode, shape) = self.base_data.settings.board();
+        let size = match mode {
+            MazeMode::ThreeD => format!("{}x{}x{}", shape.0, shape.1, shape.2),
+            MazeMode::FourD => format!("{}x{}x{}x{}", shape.0, shape.1, shape.2, shape.3),
+        };
+
+        let mut texts = vec![
+            (format!("High scores {} {}", mode, size), [10.0, 50.0]),
+            (
+                format!(
+                    "{:<14}{:>6}{:>7}{:>7}{:>6}",
+                    "   Name", "Score", "Time", "Moves", "Undos"
+                ),
+                [10.0, 100.0],
+            ),
+        ];
+        for (place, entry) in self.high_scores.board(mode, shape).iter().enumerate() {
+            texts.push((
+                format!(
+                    "{:>2} {:<11}{:>6}{:>7}{:>7}{:>6}",
+                    place + 1,
+                    entry.name.chars().take(10).collect::<String>(),
+                    entry.score,
+                    format_time(entry.time),
+                    entry.moves,
+                    entry.undos
+                ),
+                [10.0, 150.0 + f64::from(place as u32) * 40.0],
+            ));
+        }
+        texts.push((
+            format!("Press {} to go back", controls.key_name(Action::Scores)),
+            [10.0, 875.0],
+        ));
+        texts
+    }
+}
+
+impl BaseData {
+    /// This creates a new `BaseData` with a new maze made using `settings`
+    pub fn new(settings: GameSettings) -> Self {
+        let clock = GameClock::new(settings.time_attack);
+        let seed = settings.seed.unwrap_or_else(random);
+        let current_maze = settings.level_maze(0, seed);
+        let mut base_data = Self {
+            current_maze: Maze::default(),
+            start_pos: (1, 1, 1, 1),
+            end_pos: (1, 1, 1, 1),
+            settings,
+            seed,
+            first_seed: seed,
+            level: 0,
+            score: 0,
+            moves: 0,
+            level_moves: 0,
+            undos: 0,
+            level_hints: 0,
+            par: 0,
+            clock,
+        };
+        base_data.start_level(current_maze);
+        base_data
+    }
+
+    /// Starts playing `maze`, working out its par
+    pub fn start_level(&mut self, maze: Maze) {
+        self.start_pos = maze.start_pos();
+        self.end_pos = maze.end_pos();
+        self.par = par(&maze, self.settings.mode).unwrap_or(0);
+        self.level_moves = 0;
+        self.level_hints = 0;
+        self.current_maze = maze;
+    }
+
+    /// Why the exit of a generated maze is not where the settings asked for
+    /// it, if it isn't. The exits of pack levels are never checked
+    pub fn exit_problem(&self) -> Option<String> {
+        if self.settings.pack_level(self.level).is_some() {
+            return None;
+        }
+        self.settings.options.exit.check(&self.current_maze).err()
+    }
+
+    /// Counts a step or a turn onto another plane
+    pub fn count_move(&mut self) {
+        self.moves += 1;
+        self.level_moves += 1;
+    }
+}
+
+/// The points for finishing a maze of `par` in `moves` with `hints` hints. A
+/// maze finished in par is worth `PAR_POINTS` and the points go down the more
+/// moves are taken, and by `HINT_COST` for each hint, but every finished maze
+/// is worth at least 1 point
+pub fn level_score(par: usize, moves: u64, hints: u64) -> u64 {
+    let par = par as u64;
+    (PAR_POINTS * par / moves.max(par).max(1))
+        .saturating_sub(HINT_COST * hints)
+        .max(1)
+}
+
+impl Default for BaseData {
+    #[inline]
+    fn default() -> Self {
+        Self::new(GameSettings::default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{game::timer::TimeAttack, ExitPlacement};
+    use std::fs;
+
+    #[test]
+    fn moves_and_undo() {
+        let mut state = GameState::corridor();
+        state.apply(Action::MoveRight);
+        state.apply(Action::MoveRight);
+        // There is a wall in the way
+        state.apply(Action::MoveUp);
+        assert_eq!(state.view(&Controls::default()).player, (3, 1));
+        assert_eq!(state.to_save().level_moves, 2);
+
+        state.apply(Action::Undo);
+        assert_eq!(state.view(&Controls::default()).player, (2, 1));
+        assert_eq!(state.to_save().undos, 1);
+        state.apply(Action::Redo);
+        assert_eq!(state.view(&Controls::default()).player, (3, 1));
+
+        // Nothing moves while the game is paused
+        state.apply(Action::Pause);
+        state.apply(Action::MoveLeft);
+        assert_eq!(state.view(&Controls::default()).player, (3, 1));
+    }
+
+    #[test]
+    fn reaching_the_exit() {
+        let mut state = GameState::corridor();
+        for _ in 0..4 {
+            state.apply(Action::MoveRight);
+        }
+        let save = state.to_save();
+        assert_eq!(save.level, 1);
+        assert_eq!(save.score, PAR_POINTS);
+        assert_eq!(save.level_moves, 0);
+        assert_eq!(save.maze.shape(), (7, 7, 7, 3));
+    }
+
+    #[test]
+    fn continued_game_is_recorded_once() {
+        let path = |extension| {
+            std::env::temp_dir().join(format!(
+                "fourD_maze_once_{}.{}",
+                std::process::id(),
+                extension
+            ))
+        };
+        let (save_path, scores_path) = (path("save"), path("scores"));
+
+        let mut state = GameState::corridor();
+        state.base_data.settings.save_path = save_path.clone();
+        state.base_data.settings.scores_path = scores_path.clone();
+        for _ in 0..4 {
+            state.apply(Action::MoveRight);
+        }
+        state.quit();
+
+        // Continue and quit twice
+        for _ in 0..2 {
+            let save = SaveGame::load(&save_path).unwrap();
+            GameState::from_save(save, HighScores::load(&scores_path)).quit();
+        }
+
+        let (mode, shape) = state.base_data.settings.board();
+        assert_eq!(HighScores::load(&scores_path).board(mode, shape).len(), 1);
+        fs::remove_file(save_path).unwrap();
+        fs::remove_file(scores_path).unwrap();
+    }
+
+    #[test]
+    fn running_out_of_time_ends_the_game() {
+        let scores_path =
+            std::env::temp_dir().join(format!("fourD_maze_time_up_{}.scores", std::process::id()));
+        let rules = TimeAttack {
+            budget: 10.0,
+            bonus: 0.0,
+        };
+        let mut state = GameState::corridor();
+        state.base_data.settings.scores_path = scores_path.clone();
+        state.base_data.settings.time_attack = Some(rules);
+        state.base_data.clock = GameClock::new(Some(rules));
+        for _ in 0..4 {
+            state.apply(Action::MoveRight);
+        }
+        let player = state.player_pos();
+
+        state.update(20.0);
+        let (mode, shape) = state.base_data.settings.board();
+        assert_eq!(HighScores::load(&scores_path).board(mode, shape).len(), 1);
+        assert!(state
+            .view(&Controls::default())
+            .hud
+            .into_iter()
+            .any(|(text, _)| text.starts_with("Out of time! You came number 1")));
+
+        // No more moves are taken and the game is only recorded once
+        state.apply(Action::MoveRight);
+        state.update(1.0);
+        assert_eq!(state.player_pos(), player);
+        assert_eq!(state.high_scores.board(mode, shape).len(), 1);
+        fs::remove_file(scores_path).unwrap();
+    }
+
+    #[test]
+    fn waiting_for_the_next_level() {
+        let mut state = GameState::corridor();
+        state.set_auto_advance(false);
+        for _ in 0..4 {
+            state.apply(Action::MoveRight);
+        }
+        assert!(state.is_solved());
+        assert_eq!(state.level(), 0);
+
+        // The player stays on the exit until the next maze starts
+        state.apply(Action::MoveLeft);
+        assert!(state.is_solved());
+        state.next_level();
+        assert!(!state.is_solved());
+        assert_eq!(state.to_save().score, PAR_POINTS);
+    }
+
+    #[test]
+    fn walking_to_a_cell() {
+        let mut state = GameState::corridor();
+        state.walk_to((4, 1));
+        assert_eq!(state.view(&Controls::default()).player, (1, 1));
+        state.update(0.0);
+        assert_eq!(state.view(&Controls::default()).player, (2, 1));
+        state.update(WALK_INTERVAL * 2.0);
+        assert_eq!(state.view(&Controls::default()).player, (4, 1));
+
+        // Another action stops the walk
+        state.walk_to((1, 1));
+        state.update(0.0);
+        state.apply(Action::Hint);
+        state.update(1.0);
+        assert_eq!(state.view(&Controls::default()).player, (3, 1));
+
+        // Cells off the path can't be walked to
+        state.walk_to((3, 0));
+        state.update(1.0);
+        assert_eq!(state.view(&Controls::default()).player, (3, 1));
+
+        // A walk waits while the window isn't focused
+        state.walk_to((1, 1));
+        state.set_focus(false);
+        state.update(1.0);
+        assert_eq!(state.view(&Controls::default()).player, (3, 1));
+        state.set_focus(true);
+        state.update(1.0);
+        assert_eq!(state.view(&Controls::default()).player, (1, 1));
+    }
+
+    #[test]
+    fn saving_is_shown_on_the_hud() {
+        let mut state = GameState::corridor();
+        state.base_data.settings.save_path = std::env::temp_dir()
+            .join("fourD_maze no such directory")
+            .join("test.save");
+        let message = |state: &GameState| {
+            state
+                .view(&Controls::default())
+                .hud
+                .into_iter()
+                .any(|(text, _)| text.starts_with("Could not save the game"))
+        };
+
+        state.apply(Action::Save);
+        assert!(message(&state));
+        assert!(state.quit()[0].starts_with("Could not save the game"));
+
+        // The message goes once the player does something else
+        state.apply(Action::MoveRight);
+        assert!(!message(&state));
+    }
+
+    #[test]
+    fn exit_too_far_is_shown_on_the_hud() {
+        let mut settings = GameSettings::default();
+        settings.options.exit = ExitPlacement::Random { min_length: 10_000 };
+        let state = GameState::new(settings, HighScores::default());
+        assert!(state
+            .view(&Controls::default())
+            .hud
+            .into_iter()
+            .any(|(text, _)| text.starts_with("No room is 10000 steps")));
+    }
+}
@@ -0,0 +1,248 @@
+//! Playing with a gamepad. Button presses and stick movements are turned into
+//! the same `Action`s as the keys, so a gamepad can do everything a keyboard
+//! can apart from changing the controls.
+//!
+//! The buttons are numbered like an SDL game controller, which is how the
+//! SDL2 window reports them once `init_joysticks` has been called. Pressing
+//! the left stick in saves the game. Holding a direction on the d-pad or the
+//! left stick keeps moving, after `REPEAT_DELAY` and then every
+//! `REPEAT_INTERVAL`, like a held key does
+use super::controls::Action;
+use piston::input::{Button, ControllerAxisArgs, ControllerButton, GenericEvent};
+
+/// How far the stick has to be pushed before it counts, out of 1
+pub const DEAD_ZONE: f64 = 0.3;
+
+/// Seconds a direction is held before it starts repeating
+pub const REPEAT_DELAY: f64 = 0.4;
+
+/// Seconds between repeats once a direction is repeating
+pub const REPEAT_INTERVAL: f64 = 0.15;
+
+/// The action of each button
+const BUTTONS: [(u8, Action); 14] = [
+    (0, Action::DepthDown),
+    (1, Action::Redo),
+    (2, Action::Undo),
+    (3, Action::DepthUp),
+    (4, Action::Scores),
+    (6, Action::Pause),
+    (7, Action::Save),
+    (8, Action::Hint),
+    (9, Action::RotateBack),
+    (10, Action::RotateForward),
+    (11, Action::MoveUp),
+    (12, Action::MoveDown),
+    (13, Action::MoveLeft),
+    (14, Action::MoveRight),
+];
+
+/// The axes of the left stick. Y is positive downwards like the screen
+const STICK_X: u8 = 0;
+const STICK_Y: u8 = 1;
+
+/// A direction being held down, and how long until it next repeats
+#[derive(Clone, Debug, Default, PartialEq)]
+struct Repeat {
+    action: Option<Action>,
+    wait: f64,
+}
+
+/// The state of the gamepad between events
+#[derive(Clone, Debug, PartialEq)]
+pub struct Gamepad {
+    dead_zone: f64,
+    stick: [f64; 2],
+    held_stick: Repeat,
+    held_button: Repeat,
+}
+
+impl Gamepad {
+    /// A gamepad with a dead zone of `dead_zone` out of 1
+    pub fn new(dead_zone: f64) -> Self {
+        Self {
+            dead_zone,
+            stick: [0.0; 2],
+            held_stick: Repeat::default(),
+            held_button: Repeat::default(),
+        }
+    }
+
+    /// The action for a gamepad event, if there is one
+    pub fn event<E: GenericEvent>(&mut self, e: &E) -> Option<Action> {
+        if let Some(Button::Controller(button)) = e.press_args() {
+            return self.button(button, true);
+        }
+        if let Some(Button::Controller(button)) = e.release_args() {
+            return self.button(button, false);
+        }
+        e.controller_axis_args().and_then(|args| self.axis(args))
+    }
+
+    /// Handles a button being pressed or let go. Returns the action to do
+    pub fn button(&mut self, button: ControllerButton, pressed: bool) -> Option<Action> {
+        let action = BUTTONS
+            .iter()
+            .find(|&&(id, _)| id == button.button)
+            .map(|&(_, action)| action)?;
+
+        if action.player_move().is_none() {
+            return if pressed { Some(action) } else { None };
+        }
+        if pressed {
+            self.held_button.hold(Some(action))
+        } else {
+            if self.held_button.action == Some(action) {
+                self.held_button.hold(None);
+            }
+            None
+        }
+    }
+
+    /// Handles the left stick moving. The stick points whichever way it is
+    /// pushed furthest, once it is out of the dead zone. Returns the action
+    /// to do when it starts pointing a new way
+    pub fn axis(&mut self, args: ControllerAxisArgs) -> Option<Action> {
+        match args.axis {
+            STICK_X => self.stick[0] = args.position,
+            STICK_Y => self.stick[1] = args.position,
+            _ => return None,
+        }
+
+        let [x, y] = self.stick;
+        let action = if x.abs().max(y.abs()) < self.dead_zone {
+            None
+        } else if x.abs() >= y.abs() {
+            Some(if x < 0.0 {
+                Action::MoveLeft
+            } else {
+                Action::MoveRight
+            })
+        } else {
+            Some(if y < 0.0 {
+                Action::MoveUp
+            } else {
+                Action::MoveDown
+            })
+        };
+        self.held_stick.hold(action)
+    }
+
+    /// Moves the repeat timers on by `dt` seconds. Returns the moves that
+    /// repeated
+    pub fn update(&mut self, dt: f64) -> Vec<Action> {
+        let mut actions = self.held_stick.tick(dt);
+        actions.extend(self.held_button.tick(dt));
+        actions
+    }
+}
+
+impl Repeat {
+    /// Starts holding `action`, or lets go if it is `None`. Returns the
+    /// action if it is a new one
+    fn hold(&mut self, action: Option<Action>) -> Option<Action> {
+        if action == self.action {
+            return None;
+        }
+        self.action = action;
+        self.wait = REPEAT_DELAY;
+        action
+    }
+
+    fn tick(&mut self, dt: f64) -> Vec<Action> {
+        let mut repeats = Vec::new();
+        if let Some(action) = self.action {
+            self.wait -= dt;
+            while self.wait <= 0.0 {
+                repeats.push(action);
+                self.wait += REPEAT_INTERVAL;
+            }
+        }
+        repeats
+    }
+}
+
+impl Default for Gamepad {
+    fn default() -> Self {
+        Self::new(DEAD_ZONE)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use piston::input::{ButtonArgs, ButtonState, Event, Input, Motion};
+
+    fn button(state: ButtonState, id: u8) -> Event {
+        Event::Input(Input::Button(ButtonArgs {
+            state,
+            button: Button::Controller(ControllerButton::new(0, id)),
+            scancode: None,
+        }))
+    }
+
+    fn stick(axis: u8, position: f64) -> Event {
+        Event::Input(Input::Move(Motion::ControllerAxis(
+            ControllerAxisArgs::new(0, axis, position),
+        )))
+    }
+
+    #[test]
+    fn buttons() {
+        let mut gamepad = Gamepad::default();
+        assert_eq!(
+            gamepad.event(&button(ButtonState::Press, 10)),
+            Some(Action::RotateForward)
+        );
+        assert_eq!(gamepad.event(&button(ButtonState::Release, 10)), None);
+        assert_eq!(gamepad.event(&button(ButtonState::Press, 5)), None);
+        assert_eq!(
+            gamepad.event(&button(ButtonState::Press, 7)),
+            Some(Action::Save)
+        );
+
+        // Only moves repeat
+        assert_eq!(
+            gamepad.event(&button(ButtonState::Press, 2)),
+            Some(Action::Undo)
+        );
+        assert!(gamepad.update(5.0).is_empty());
+    }
+
+    #[test]
+    fn dead_zone() {
+        let mut gamepad = Gamepad::default();
+        assert_eq!(gamepad.event(&stick(STICK_X, 0.2)), None);
+        assert_eq!(gamepad.event(&stick(STICK_Y, -0.25)), None);
+        assert_eq!(gamepad.event(&stick(STICK_Y, -0.5)), Some(Action::MoveUp));
+        // Still up, so nothing new happens
+        assert_eq!(gamepad.event(&stick(STICK_Y, -0.9)), None);
+        assert_eq!(
+            gamepad.event(&stick(STICK_X, 0.95)),
+            Some(Action::MoveRight)
+        );
+        assert_eq!(gamepad.event(&stick(STICK_X, 0.0)), Some(Action::MoveUp));
+        assert_eq!(gamepad.event(&stick(STICK_Y, 0.1)), None);
+        assert!(gamepad.update(1.0).is_empty());
+        // The right stick isn't used
+        assert_eq!(gamepad.event(&stick(3, 1.0)), None);
+    }
+
+    #[test]
+    fn held_directions_repeat() {
+        let mut gamepad = Gamepad::default();
+        assert_eq!(
+            gamepad.event(&button(ButtonState::Press, 13)),
+            Some(Action::MoveLeft)
+        );
+        assert!(gamepad.update(REPEAT_DELAY / 2.0).is_empty());
+        assert_eq!(gamepad.update(REPEAT_DELAY / 2.0), vec![Action::MoveLeft]);
+        assert_eq!(
+            gamepad.update(REPEAT_INTERVAL * 2.0),
+            vec![Action::MoveLeft, Action::MoveLeft]
+        );
+
+        gamepad.event(&button(ButtonState::Release, 13));
+        assert!(gamepad.update(1.0).is_empty());
+    }
+}
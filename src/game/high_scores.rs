@@ -0,0 +1,396 @@
+//! The high-score table. Every finished game is recorded on the board for its
+//! maze size and mode, so a quick 3D game isn't compared with a huge 4D one.
+//!
+//! The table is kept in a small text file with a `4d-maze-scores <version>`
+//! line followed by one tab separated line per score
+use crate::{MazeMode, MazePos};
+use std::{
+    cmp::Ordering,
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+/// Where the scores are kept
+pub const DEFAULT_SCORES_FILE: &str = "fourD_maze.scores";
+
+/// How many scores each board keeps
+pub const BOARD_SIZE: usize = 10;
+
+const SCORES_HEADER: &str = "4d-maze-scores 1";
+
+/// One finished game
+#[derive(Clone, Debug, PartialEq)]
+pub struct ScoreEntry {
+    pub name: String,
+    pub score: u64,
+    /// The time played in seconds
+    pub time: f64,
+    pub moves: u64,
+    /// How many moves were undone
+    pub undos: u64,
+    /// The seed of the first maze of the game
+    pub seed: u64,
+    pub mode: MazeMode,
+    pub shape: MazePos,
+}
+
+/// Every board of the high-score table
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct HighScores {
+    entries: Vec<ScoreEntry>,
+    /// Set when the file couldn't be read, so the scores are only kept in
+    /// memory and the file is left alone
+    in_memory: bool,
+}
+
+impl HighScores {
+    /// Loads the table from `path`. A missing file is an empty table. A file
+    /// that can't be parsed is moved to `<path>.<n>.bak` so it isn't lost, and
+    /// an empty table is used instead. If the file can't be read at all the
+    /// table is only kept in memory and the file is never written
+    pub fn load<P: AsRef<Path>>(path: P) -> Self {
+        let path = path.as_ref();
+        let bytes = match fs::read(path) {
+            Ok(bytes) => bytes,
+            Err(ref error) if error.kind() == io::ErrorKind::NotFound => return Self::default(),
+            Err(error) => {
+                eprintln!(
+                    "Could not read the high scores in {}, new scores won't be saved: {}",
+                    path.display(),
+                    error
+                );
+                return Self {
+                    in_memory: true,
+                    ..Self::default()
+                };
+            }
+        };
+
+        match String::from_utf8(bytes)
+            .ok()
+            .and_then(|text| Self::from_text(&text))
+        {
+            Some(scores) => scores,
+            None => {
+                let backup = backup_path(path);
+                eprintln!(
+                    "The high scores in {} are broken, they have been moved to {}",
+                    path.display(),
+                    backup.display()
+                );
+                if let Err(error) = fs::rename(path, &backup) {
+                    eprintln!("Could not move the high scores: {}", error);
+                }
+                Self::default()
+            }
+        }
+    }
+
+    /// Writes the table to `path`, unless it is only kept in memory
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        if self.in_memory {
+            return Ok(());
+        }
+        fs::write(path, self.to_text())
+    }
+
+    /// Records `entry` on its board. A game that is continued from a save is
+    /// recorded each time it is quit, so an entry with the same name and first
+    /// seed is the same game and only the better of the two is kept.
+    ///
+    /// Returns the place on the board (counting from 0) of the entry that was
+    /// kept, or `None` if it didn't make the board
+    pub fn add(&mut self, entry: ScoreEntry) -> Option<usize> {
+        let (mode, shape) = (entry.mode, entry.shape);
+        let same_game = |other: &ScoreEntry| {
+            other.mode == mode
+                && other.shape == shape
+                && other.seed == entry.seed
+                && other.name == entry.name
+        };
+        if let Some(index) = self.entries.iter().position(same_game) {
+            if compare(&self.entries[index], &entry) != Ordering::Greater {
+                let earlier = &self.entries[index];
+                return self
+                    .board(mode, shape)
+                    .iter()
+                    .position(|other| *other == earlier);
+            }
+            self.entries.remove(index);
+        }
+
+        let place = self
+            .board(mode, shape)
+            .iter()
+            .take_while(|other| compare(other, &entry) != Ordering::Greater)
+            .count();
+        if place >= BOARD_SIZE {
+            return None;
+        }
+
+        self.entries.push(entry);
+        self.entries.sort_by(compare);
+
+        // Drop whatever fell off the bottom of the board
+        let mut kept = 0;
+        self.entries.retain(|other| {
+            if other.mode != mode || other.shape != shape {
+                return true;
+            }
+            kept += 1;
+            kept <= BOARD_SIZE
+        });
+        Some(place)
+    }
+
+    /// The board for mazes of `shape` in `mode`, best first
+    pub fn board(&self, mode: MazeMode, shape: MazePos) -> Vec<&ScoreEntry> {
+        self.entries
+            .iter()
+            .filter(|entry| entry.mode == mode && entry.shape == shape)
+            .collect()
+    }
+
+    fn to_text(&self) -> String {
+        let mut text = format!("{}\n", SCORES_HEADER);
+        for entry in &self.entries {
+            let (x, y, z, w) = entry.shape;
+            text += &format!(
+                "{}\t{}x{}x{}x{}\t{}\t{:.3}\t{}\t{}\t{}\t{}\n",
+                entry.mode,
+                x,
+                y,
+                z,
+                w,
+                entry.score,
+                entry.time,
+                entry.moves,
+                entry.undos,
+                entry.seed,
+                // A tab would split the name and a line break would end it
+                entry
+                    .name
+                    .replace(|ch| ch == '\t' || ch == '\n' || ch == '\r', " ")
+            );
+        }
+        text
+    }
+
+    fn from_text(text: &str) -> Option<Self> {
+        let mut lines = text.lines();
+        if lines.next()? != SCORES_HEADER {
+            return None;
+        }
+
+        let mut entries = lines
+            .map(|line| {
+                let fields = line.splitn(8, '\t').collect::<Vec<_>>();
+                match fields.as_slice() {
+                    [mode, shape, score, time, moves, undos, seed, name] => Some(ScoreEntry {
+                        name: name.to_string(),
+                        score: score.parse().ok()?,
+                        time: time.parse().ok().filter(|time: &f64| time.is_finite())?,
+                        moves: moves.parse().ok()?,
+                        undos: undos.parse().ok()?,
+                        seed: seed.parse().ok()?,
+                        mode: match *mode {
+                            "3D" => MazeMode::ThreeD,
+                            "4D" => MazeMode::FourD,
+                            _ => return None,
+                        },
+                        shape: parse_shape(shape)?,
+                    }),
+                    _ => None,
+                }
+            })
+            .collect::<Option<Vec<_>>>()?;
+        entries.sort_by(compare);
+
+        Some(Self {
+            entries,
+            in_memory: false,
+        })
+    }
+}
+
+/// Orders scores best first: the highest score, then the quickest, then the
+/// fewest moves
+fn compare(first: &ScoreEntry, second: &ScoreEntry) -> Ordering {
+    second
+        .score
+        .cmp(&first.score)
+        .then(
+            first
+                .time
+                .partial_cmp(&second.time)
+                .unwrap_or(Ordering::Equal),
+        )
+        .then(first.moves.cmp(&second.moves))
+}
+
+/// The first `<path>.<n>.bak` that doesn't exist yet, so no earlier backup is
+/// overwritten
+fn backup_path(path: &Path) -> PathBuf {
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+    (1..)
+        .map(|n| path.with_file_name(format!("{}.{}.bak", name, n)))
+        .find(|backup| !backup.exists())
+        .unwrap()
+}
+
+fn parse_shape(shape: &str) -> Option<MazePos> {
+    let axes = shape
+        .split('x')
+        .map(|axis| axis.parse().ok())
+        .collect::<Option<Vec<usize>>>()?;
+    match axes.as_slice() {
+        [x, y, z, w] => Some((*x, *y, *z, *w)),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(name: &str, score: u64, time: f64, shape: MazePos) -> ScoreEntry {
+        ScoreEntry {
+            name: name.to_string(),
+            score,
+            time,
+            moves: score * 10,
+            undos: 1,
+            seed: 5,
+            mode: MazeMode::FourD,
+            shape,
+        }
+    }
+
+    #[test]
+    fn boards_are_separate_and_sorted() {
+        let mut scores = HighScores::default();
+        assert_eq!(scores.add(entry("Ann", 3, 60.0, (9, 9, 9, 9))), Some(0));
+        assert_eq!(scores.add(entry("Bob", 5, 90.0, (9, 9, 9, 9))), Some(0));
+        assert_eq!(scores.add(entry("Cat", 5, 80.0, (9, 9, 9, 9))), Some(0));
+        assert_eq!(scores.add(entry("Dan", 1, 10.0, (7, 7, 7, 7))), Some(0));
+
+        let names = |shape| {
+            scores
+                .board(MazeMode::FourD, shape)
+                .iter()
+                .map(|entry| entry.name.clone())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(names((9, 9, 9, 9)), vec!["Cat", "Bob", "Ann"]);
+        assert_eq!(names((7, 7, 7, 7)), vec!["Dan"]);
+        assert!(scores.board(MazeMode::ThreeD, (7, 7, 7, 3)).is_empty());
+    }
+
+    #[test]
+    fn boards_are_capped() {
+        let mut scores = HighScores::default();
+        // Each of Ann's games starts from a different seed
+        for score in 1..=BOARD_SIZE as u64 {
+            scores.add(ScoreEntry {
+                seed: score,
+                ..entry("Ann", score, 1.0, (9, 9, 9, 9))
+            });
+        }
+        assert_eq!(scores.add(entry("Bob", 0, 1.0, (9, 9, 9, 9))), None);
+        assert_eq!(scores.add(entry("Cat", 100, 1.0, (9, 9, 9, 9))), Some(0));
+
+        let board = scores.board(MazeMode::FourD, (9, 9, 9, 9));
+        assert_eq!(board.len(), BOARD_SIZE);
+        assert_eq!(board.last().unwrap().score, 2);
+    }
+
+    #[test]
+    fn same_game_is_kept_once() {
+        let mut scores = HighScores::default();
+        scores.add(entry("Bob", 4, 1.0, (9, 9, 9, 9)));
+        assert_eq!(scores.add(entry("Ann", 3, 60.0, (9, 9, 9, 9))), Some(1));
+        // Quitting again after continuing, with more points
+        assert_eq!(scores.add(entry("Ann", 5, 90.0, (9, 9, 9, 9))), Some(0));
+        // Quitting again without scoring only makes the time worse
+        assert_eq!(scores.add(entry("Ann", 5, 95.0, (9, 9, 9, 9))), Some(0));
+
+        let board = scores.board(MazeMode::FourD, (9, 9, 9, 9));
+        assert_eq!(board.len(), 2);
+        assert_eq!((board[0].score, board[0].time), (5, 90.0));
+    }
+
+    #[test]
+    fn text_round_trip() {
+        let mut scores = HighScores::default();
+        scores.add(entry("Ann Smith", 3, 61.25, (9, 9, 9, 9)));
+        scores.add(ScoreEntry {
+            mode: MazeMode::ThreeD,
+            ..entry("Bob", 2, 5.5, (21, 21, 5, 3))
+        });
+        assert_eq!(HighScores::from_text(&scores.to_text()), Some(scores));
+    }
+
+    #[test]
+    fn names_stay_on_their_line() {
+        let mut scores = HighScores::default();
+        scores.add(entry("Ann\tB\nC\r\nD", 3, 1.0, (9, 9, 9, 9)));
+        let loaded = HighScores::from_text(&scores.to_text()).unwrap();
+        assert_eq!(
+            loaded.board(MazeMode::FourD, (9, 9, 9, 9))[0].name,
+            "Ann B C  D"
+        );
+    }
+
+    #[test]
+    fn corrupt_file_is_backed_up() {
+        let path = std::env::temp_dir().join(format!("fourD_maze_{}.scores", std::process::id()));
+        let backups = [1, 2]
+            .iter()
+            .map(|n| {
+                path.with_file_name(format!(
+                    "fourD_maze_{}.scores.{}.bak",
+                    std::process::id(),
+                    n
+                ))
+            })
+            .collect::<Vec<_>>();
+        for broken in &["not a size", "not a shape"] {
+            fs::write(
+                &path,
+                format!("4d-maze-scores 1\n4D\t{}\t1\t2\t3\t0\t4\tAnn\n", broken),
+            )
+            .unwrap();
+            assert_eq!(HighScores::load(&path), HighScores::default());
+            assert!(!path.exists());
+        }
+
+        // The second backup doesn't overwrite the first
+        assert!(fs::read_to_string(&backups[0])
+            .unwrap()
+            .contains("not a size"));
+        assert!(fs::read_to_string(&backups[1])
+            .unwrap()
+            .contains("not a shape"));
+        for backup in backups {
+            fs::remove_file(backup).unwrap();
+        }
+
+        // A missing file is just an empty table
+        assert_eq!(HighScores::load(&path), HighScores::default());
+    }
+
+    #[test]
+    fn unreadable_file_is_left_alone() {
+        // A directory can't be read as a file
+        let path = std::env::temp_dir().join(format!("fourD_maze_{}.dir", std::process::id()));
+        fs::create_dir(&path).unwrap();
+
+        let mut scores = HighScores::load(&path);
+        assert!(scores.board(MazeMode::FourD, (9, 9, 9, 9)).is_empty());
+        scores.add(entry("Ann", 3, 60.0, (9, 9, 9, 9)));
+        scores.save(&path).unwrap();
+        assert!(path.is_dir());
+        assert_eq!(scores.board(MazeMode::FourD, (9, 9, 9, 9)).len(), 1);
+        fs::remove_dir(path).unwrap();
+    }
+}
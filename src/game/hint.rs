@@ -0,0 +1,166 @@
+//! Hints for a player who is stuck. A hint follows the quickest route to the
+//! exit from where the player is, counting turns onto other planes as moves
+use crate::{solve_with_rotations, Maze, MazeAxis4, MazeMode, MazePos};
+
+/// How many cells of the route a hint shows
+pub const HINT_LENGTH: usize = 5;
+
+/// The points taken off a maze for each hint
+pub const HINT_COST: u64 = 10;
+
+/// What the player should do next
+#[derive(Clone, Debug, PartialEq)]
+pub enum Hint {
+    /// The next cells to walk through on the plane the player is looking at
+    Path(Vec<MazePos>),
+    /// The next step is along the depth axis of the plane, up it if
+    /// `positive` is true
+    Depth { positive: bool },
+    /// The route leaves this plane, so the player should turn onto `plane`,
+    /// which takes `turns` presses of the rotate forward key, or the rotate
+    /// back key if `forward` is false
+    Turn {
+        plane: MazeAxis4,
+        turns: usize,
+        forward: bool,
+    },
+}
+
+impl Hint {
+    /// The hint for a player at `pos` looking at `plane`. `Path` has at most
+    /// `length` cells. Returns `None` if the player is on the exit or it
+    /// can't be reached
+    pub fn find(
+        maze: &Maze,
+        pos: MazePos,
+        plane: MazeAxis4,
+        mode: MazeMode,
+        length: usize,
+    ) -> Option<Self> {
+        let route = solve_with_rotations(maze, pos, maze.end_pos(), plane, mode)?;
+        let &(next, _) = route.get(1)?;
+        let depth = |pos: MazePos| [pos.0, pos.1, pos.2, pos.3][plane.depth_axis()];
+
+        if next == pos {
+            let turns = route
+                .iter()
+                .skip(1)
+                .take_while(|&&(next, _)| next == pos)
+                .count();
+            let mut turned = plane;
+            turned.cycle(mode);
+            Some(Hint::Turn {
+                plane: route[turns].1,
+                turns,
+                forward: route[1].1 == turned,
+            })
+        } else if depth(next) != depth(pos) {
+            Some(Hint::Depth {
+                positive: depth(next) > depth(pos),
+            })
+        } else {
+            // Only the cells on the slice being shown can be marked
+            Some(Hint::Path(
+                route
+                    .iter()
+                    .skip(1)
+                    .take_while(|&&(next, next_plane)| {
+                        next_plane == plane
+                            && next == plane.with_plane_coords(pos, plane.plane_coords(next))
+                    })
+                    .take(length)
+                    .map(|&(next, _)| next)
+                    .collect(),
+            ))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MazeCell;
+
+    /// An L shaped corridor that goes along X and then along `axis`
+    fn corner(axis: usize) -> Maze {
+        let mut shape = [9, 3, 3, 3];
+        shape[axis] = 7;
+        let mut maze = Maze::new_empty((shape[0], shape[1], shape[2], shape[3]));
+        for x in 1..8 {
+            maze.0[(x, 1, 1, 1)] = MazeCell::Cell;
+        }
+        let mut end = [7, 1, 1, 1];
+        for coord in 1..6 {
+            end[axis] = coord;
+            maze.0[(end[0], end[1], end[2], end[3])] = MazeCell::Cell;
+        }
+        maze.0[(1, 1, 1, 1)] = MazeCell::Start;
+        maze.0[(end[0], end[1], end[2], end[3])] = MazeCell::End;
+        maze
+    }
+
+    #[test]
+    fn path_on_the_plane() {
+        let maze = corner(2);
+        let hint = Hint::find(&maze, (1, 1, 1, 1), MazeAxis4::XY, MazeMode::FourD, 3);
+        assert_eq!(
+            hint,
+            Some(Hint::Path(vec![(2, 1, 1, 1), (3, 1, 1, 1), (4, 1, 1, 1)]))
+        );
+
+        // The path stops where the route leaves the slice
+        let hint = Hint::find(&maze, (5, 1, 1, 1), MazeAxis4::XY, MazeMode::FourD, 5);
+        assert_eq!(hint, Some(Hint::Path(vec![(6, 1, 1, 1), (7, 1, 1, 1)])));
+
+        // XZ shows the whole route
+        let hint = Hint::find(&maze, (5, 1, 1, 1), MazeAxis4::XZ, MazeMode::FourD, 3);
+        assert_eq!(
+            hint,
+            Some(Hint::Path(vec![(6, 1, 1, 1), (7, 1, 1, 1), (7, 1, 2, 1)]))
+        );
+    }
+
+    #[test]
+    fn along_the_depth_axis() {
+        // Z is the depth axis of XY
+        let maze = corner(2);
+        let hint = Hint::find(&maze, (7, 1, 1, 1), MazeAxis4::XY, MazeMode::ThreeD, 5);
+        assert_eq!(hint, Some(Hint::Depth { positive: true }));
+        let hint = Hint::find(&maze, (7, 1, 3, 1), MazeAxis4::XY, MazeMode::ThreeD, 5);
+        assert_eq!(hint, Some(Hint::Depth { positive: true }));
+    }
+
+    #[test]
+    fn turn_off_the_plane() {
+        // W can only be walked along from XW, YW and ZW
+        let maze = corner(3);
+        let hint = Hint::find(&maze, (7, 1, 1, 1), MazeAxis4::XY, MazeMode::FourD, 5);
+        assert_eq!(
+            hint,
+            Some(Hint::Turn {
+                plane: MazeAxis4::ZW,
+                turns: 1,
+                forward: false
+            })
+        );
+        let hint = Hint::find(&maze, (7, 1, 1, 1), MazeAxis4::YZ, MazeMode::FourD, 5);
+        assert_eq!(
+            hint,
+            Some(Hint::Turn {
+                plane: MazeAxis4::YW,
+                turns: 1,
+                forward: true
+            })
+        );
+    }
+
+    #[test]
+    fn no_hint_at_the_exit() {
+        let maze = corner(2);
+        let end = maze.end_pos();
+        assert_eq!(
+            Hint::find(&maze, end, MazeAxis4::XY, MazeMode::FourD, 5),
+            None
+        );
+    }
+}
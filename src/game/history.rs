@@ -0,0 +1,111 @@
+//! The undo and redo history of the player. Only the last `HISTORY_SIZE`
+//! moves are kept so a long game doesn't keep using more memory
+use std::collections::VecDeque;
+
+/// How many moves can be undone
+pub const HISTORY_SIZE: usize = 100;
+
+/// A bounded history of states that can be stepped back and forward through
+#[derive(Clone, Debug, PartialEq)]
+pub struct History<T> {
+    undo: VecDeque<T>,
+    redo: Vec<T>,
+    size: usize,
+}
+
+impl<T> History<T> {
+    /// An empty history that keeps the last `size` states
+    pub fn new(size: usize) -> Self {
+        Self {
+            undo: VecDeque::with_capacity(size),
+            redo: Vec::new(),
+            size,
+        }
+    }
+
+    /// Remembers `state`, the state before a move. A new move can't be
+    /// redone past so the redo history is forgotten
+    pub fn record(&mut self, state: T) {
+        self.redo.clear();
+        if self.size == 0 {
+            return;
+        }
+        if self.undo.len() == self.size {
+            self.undo.pop_front();
+        }
+        self.undo.push_back(state);
+    }
+
+    /// Goes back one move from `current`. Returns the state to go back to or
+    /// `None` if there is nothing to undo
+    pub fn undo(&mut self, current: T) -> Option<T> {
+        let state = self.undo.pop_back()?;
+        self.redo.push(current);
+        Some(state)
+    }
+
+    /// Goes forward one move that was undone from `current`. Returns the
+    /// state to go to or `None` if there is nothing to redo
+    pub fn redo(&mut self, current: T) -> Option<T> {
+        let state = self.redo.pop()?;
+        self.undo.push_back(current);
+        Some(state)
+    }
+
+    /// Forgets everything, ready for a new maze
+    pub fn clear(&mut self) {
+        self.undo.clear();
+        self.redo.clear();
+    }
+}
+
+impl<T> Default for History<T> {
+    fn default() -> Self {
+        Self::new(HISTORY_SIZE)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn undo_and_redo() {
+        let mut history = History::new(10);
+        history.record(1);
+        history.record(2);
+
+        assert_eq!(history.undo(3), Some(2));
+        assert_eq!(history.undo(2), Some(1));
+        assert_eq!(history.undo(1), None);
+        assert_eq!(history.redo(1), Some(2));
+        assert_eq!(history.redo(2), Some(3));
+        assert_eq!(history.redo(3), None);
+    }
+
+    #[test]
+    fn new_moves_forget_redo() {
+        let mut history = History::new(10);
+        history.record(1);
+        assert_eq!(history.undo(2), Some(1));
+        history.record(1);
+        assert_eq!(history.redo(5), None);
+        assert_eq!(history.undo(5), Some(1));
+    }
+
+    #[test]
+    fn bounded() {
+        let mut history = History::new(3);
+        for state in 0..10 {
+            history.record(state);
+        }
+
+        let mut current = 10;
+        let mut undone = Vec::new();
+        while let Some(state) = history.undo(current) {
+            undone.push(state);
+            current = state;
+        }
+        assert_eq!(undone, vec![9, 8, 7]);
+    }
+}
@@ -1,8 +1,22 @@
-use crate::{gen_maze, Maze, MazeAxis3, MazeCell, MazeSlice};
+use super::{
+    controls::{Keyboard, DEFAULT_CONTROLS_FILE},
+    game_state::{GameState, View},
+    gamepad::Gamepad,
+    high_scores::{HighScores, DEFAULT_SCORES_FILE},
+    mouse::{Mouse, MouseInput},
+    save::DEFAULT_SAVE_FILE,
+    timer::TimeAttack,
+};
+use crate::{
+    gen_maze_with_options, seeded_rng, Algorithm, ExitPlacement, LevelPack, Maze, MazeCell,
+    MazeMode, MazeOptions, MazePos, MazeSlice, SavedMaze, StartPlacement,
+};
 use graphics::{
-    character::CharacterCache, Context, Ellipse, Graphics, Image, Rectangle, Transformed,
+    character::CharacterCache, types::Color as Colour, Context, Ellipse, Graphics, Image, Polygon,
+    Rectangle, Transformed,
 };
-use piston::input::{keyboard::Key, Button, GenericEvent};
+use piston::input::{Button, GenericEvent};
+use std::path::PathBuf;
 
 /// Constants that define colours that the game uses
 pub mod colours {
@@ -14,48 +28,72 @@ pub mod colours {
     pub const RED: Colour = [1.0, 0.0, 0.0, 1.0];
     pub const PINK: Colour = [1.0, 0.0, 1.0, 1.0];
     pub const YELLOW: Colour = [1.0, 1.0, 0.0, 1.0];
+    pub const ORANGE: Colour = [1.0, 0.5, 0.0, 1.0];
+    pub const PURPLE: Colour = [0.5, 0.0, 0.5, 1.0];
+    pub const CYAN: Colour = [0.0, 0.7, 0.7, 1.0];
 }
 
 /// The scale up that everything uses
 const SCALE_FACTOR: f64 = 65.0;
 
 /// The offset from 0, 0 that everything uses
-const OFFSET_FACTOR: f64 = 130.0;
+pub const OFFSET_FACTOR: f64 = 130.0;
+
+/// The width and height the slice has to fit in when it is drawn
+const DRAW_AREA: f64 = 640.0;
 
-/// `BaseData` is a struct that contains data that is meant to be stored for a
-/// long time (i.e. the whole game). It contains 2 fields:
+/// `GameSettings` holds the choices made when the game is started. It
+/// contains 10 fields:
 ///
-/// * `current_maze` which holds the current maze
-/// * `end_pos` holds the position of `MazeCell::End`
-/// * `score` which contains the user's score
-pub struct BaseData {
-    current_maze: Maze,
-    end_pos: (usize, usize, usize),
-    score: u64,
+/// * `mode` which holds whether the game is played in 3D or 4D
+/// * `shape` which holds how many cells long each axis of the maze is
+/// * `seed` which the first maze is generated from if one is given
+/// * `algorithm` which holds the algorithm every maze is carved with
+/// * `options` which holds where the start and end of every maze go
+/// * `pack` which holds the levels that are played before any mazes are
+/// generated
+/// * `save_path` which holds the file the game is saved to
+/// * `scores_path` which holds the file the high scores are kept in
+/// * `player_name` which holds the name the high scores are recorded under
+/// * `time_attack` which holds the time limit if the game is played against
+/// the clock
+#[derive(Clone, Debug, PartialEq)]
+pub struct GameSettings {
+    pub mode: MazeMode,
+    pub shape: MazePos,
+    pub seed: Option<u64>,
+    pub algorithm: Algorithm,
+    pub options: MazeOptions,
+    pub pack: Option<LevelPack>,
+    pub save_path: PathBuf,
+    pub scores_path: PathBuf,
+    pub player_name: String,
+    pub time_attack: Option<TimeAttack>,
 }
 
-/// `CurrentData` is a struct that contains data that is meant to be stored for
-/// a short time (i.e. the current level). It contains 4 fields:
+/// `CurrentData` plays a `GameState` in a piston window. It contains 4
+/// fields:
 ///
-/// * `player_pos` which holds the player's `player_pos`
-/// * `cut_axis` which contains current axis the data is cut through
-/// * `cut_pos` which contains `player_pos` the cut will be made in
-/// * `base_data` which contains the `BaseData` for the game
-///
-/// `cut_axis` and `cut_pos` are used to construct the slice 'on the go'
+/// * `state` which holds the game
+/// * `keyboard` which turns keys into actions and holds the rebinding screen
+/// * `gamepad` which holds the buttons and stick directions being held
+/// * `mouse` which holds where the cursor is
 pub struct CurrentData {
-    player_pos: (usize, usize, usize),
-    cut_axis: MazeAxis3,
-    base_data: BaseData,
+    state: GameState,
+    keyboard: Keyboard,
+    gamepad: Gamepad,
+    mouse: Mouse,
 }
 
 impl CurrentData {
-    /// This creates a new `CurrentData` from a base data
-    pub fn new() -> Self {
+    /// This wraps `state` to be played in a piston window with the controls
+    /// in `DEFAULT_CONTROLS_FILE`
+    pub fn new(state: GameState) -> Self {
         Self {
-            player_pos: (1, 1, 1),
-            cut_axis: MazeAxis3::XY,
-            base_data: BaseData::default(),
+            state,
+            keyboard: Keyboard::load(DEFAULT_CONTROLS_FILE),
+            gamepad: Gamepad::default(),
+            mouse: Mouse::default(),
         }
     }
 
@@ -67,67 +105,93 @@ impl CurrentData {
     where
         C: CharacterCache<Texture = G::Texture>,
     {
-        // Create a 2D slice
-        let mz = self.create_slice();
+        let view = self.state.view(self.keyboard.controls());
+        // The rebinding screen and the leaderboard cover the maze
+        if let Some(texts) = self.keyboard.overlay().or_else(|| view.overlay.clone()) {
+            draw_lines(&texts, glyphs, c, g);
+            return;
+        }
+
+        let mz = &view.slice;
+        let scale = scale_factor(mz);
         // Iterate through the maze
         for ((x_pos, y_pos), cell) in mz.0.indexed_iter() {
-            let x_pos = f64::from(x_pos as u32) * SCALE_FACTOR + OFFSET_FACTOR;
-            let y_pos = f64::from(y_pos as u32) * SCALE_FACTOR + OFFSET_FACTOR;
+            let x_pos = f64::from(x_pos as u32) * scale + OFFSET_FACTOR;
+            let y_pos = f64::from(y_pos as u32) * scale + OFFSET_FACTOR;
 
             // In the first argument of .draw() consists of a 4-element float array with the
             // elements of the array controlling: 1 - Left/right player_pos
             // 2 - Up/down direction
             // 3 - Left/right width
             // 4 - Up/down width
-            Rectangle::new(match cell {
-                MazeCell::Cell => colours::BLACK,
-                MazeCell::Wall => colours::BLUE,
-                MazeCell::OuterWall => colours::GREEN,
-                MazeCell::Start => colours::PINK,
-                MazeCell::End => colours::RED,
-                _ => unreachable!(),
-            })
-            .draw(
-                [
-                    x_pos,
-                    y_pos,
-                    /* = 1 * SCALE_FACTOR */ SCALE_FACTOR,
-                    SCALE_FACTOR,
-                ],
+            Rectangle::new(cell_colour(cell)).draw(
+                [x_pos, y_pos, /* = 1 * scale */ scale, scale],
                 &c.draw_state,
                 c.transform,
                 g,
             )
         }
 
-        self.draw_player(&c, g);
-        self.draw_text(glyphs, c, g);
+        self.draw_depth_cues(&view, c, g);
+        self.draw_hint(&view, c, g);
+        self.draw_player(&view, c, g);
+        self.draw_text(&view, glyphs, c, g);
     }
 
+    /// Marks the cells of the slice that are open along the hidden axes. An
+    /// opening up an axis is a triangle pointing up in the top of the cell and
+    /// one down an axis points down from the bottom. The depth axis, which
+    /// PageUp and PageDown move along, is marked on the left in purple and
+    /// the other hidden axis on the right in cyan
     #[allow(clippy::cast_possible_truncation)]
-    pub fn draw_player<G: Graphics>(&self, c: &Context, g: &mut G) {
-        let (pos_x_like, pos_y_like) = match self.cut_axis {
-            MazeAxis3::XY => (
-                f64::from(self.player_pos.0 as u32) * SCALE_FACTOR + OFFSET_FACTOR,
-                f64::from(self.player_pos.1 as u32) * SCALE_FACTOR + OFFSET_FACTOR,
-            ),
-            MazeAxis3::XZ => (
-                f64::from(self.player_pos.0 as u32) * SCALE_FACTOR + OFFSET_FACTOR,
-                f64::from(self.player_pos.2 as u32) * SCALE_FACTOR + OFFSET_FACTOR,
-            ),
-            MazeAxis3::YZ => (
-                f64::from(self.player_pos.1 as u32) * SCALE_FACTOR + OFFSET_FACTOR,
-                f64::from(self.player_pos.2 as u32) * SCALE_FACTOR + OFFSET_FACTOR,
-            ),
-        };
+    pub fn draw_depth_cues<G: Graphics>(&self, view: &View, c: &Context, g: &mut G) {
+        let scale = scale_factor(&view.slice);
+        let (first, _) = view.plane.hidden_axes();
+
+        for &((x, y), dir) in &view.depth_cues {
+            let x_pos = f64::from(x as u32) * scale + OFFSET_FACTOR;
+            let y_pos = f64::from(y as u32) * scale + OFFSET_FACTOR;
+            let (triangle, colour) = cue_triangle(
+                [x_pos, y_pos, scale, scale],
+                dir.axis == first,
+                dir.positive,
+            );
+
+            Polygon::new(colour).draw(&triangle, &c.draw_state, c.transform, g);
+        }
+    }
+
+    /// Marks the cells of the hint, if one is being shown
+    #[allow(clippy::cast_possible_truncation)]
+    pub fn draw_hint<G: Graphics>(&self, view: &View, c: &Context, g: &mut G) {
+        let scale = scale_factor(&view.slice);
+        for &(x_pos, y_pos) in &view.hint {
+            let x_pos = f64::from(x_pos as u32) * scale + OFFSET_FACTOR;
+            let y_pos = f64::from(y_pos as u32) * scale + OFFSET_FACTOR;
+
+            Rectangle::new(colours::ORANGE).draw(
+                [
+                    x_pos + scale / 4.0,
+                    y_pos + scale / 4.0,
+                    scale / 2.0,
+                    scale / 2.0,
+                ],
+                &c.draw_state,
+                c.transform,
+                g,
+            );
+        }
+    }
+
+    #[allow(clippy::cast_possible_truncation)]
+    pub fn draw_player<G: Graphics>(&self, view: &View, c: &Context, g: &mut G) {
+        let scale = scale_factor(&view.slice);
+        let (pos_x_like, pos_y_like) = view.player;
+        let pos_x_like = f64::from(pos_x_like as u32) * scale + OFFSET_FACTOR;
+        let pos_y_like = f64::from(pos_y_like as u32) * scale + OFFSET_FACTOR;
 
         Ellipse::new(colours::YELLOW).draw(
-            [
-                pos_x_like,
-                pos_y_like,
-                /* = 1 * SCALE_FACTOR */ SCALE_FACTOR,
-                SCALE_FACTOR,
-            ],
+            [pos_x_like, pos_y_like, /* = 1 * scale */ scale, scale],
             &c.draw_state,
             c.transform,
             g,
@@ -135,177 +199,219 @@ impl CurrentData {
     }
 
     pub fn events<E: GenericEvent>(&mut self, e: &E) {
+        // The clocks stop while the player is in another window
+        if let Some(focused) = e.focus_args() {
+            self.state.set_focus(focused);
+        }
+
         if let Some(Button::Keyboard(key)) = e.press_args() {
-            // Create a 2D slice
-            let mz = self.create_slice();
-
-            // Create value of x, y and z from player pos
-            let (x, y, z) = self.player_pos;
-
-            match key {
-                // Movement
-                Key::Left => match self.cut_axis {
-                    MazeAxis3::XY if mz.0.get((x - 1, y)).unwrap().is_traversable() => {
-                        self.player_pos.0 -= 1
-                    }
-                    MazeAxis3::XZ if mz.0.get((x - 1, z)).unwrap().is_traversable() => {
-                        self.player_pos.0 -= 1
-                    }
-                    MazeAxis3::YZ if mz.0.get((y - 1, z)).unwrap().is_traversable() => {
-                        self.player_pos.1 -= 1
-                    }
-                    _ => {}
-                },
-                Key::Right => match self.cut_axis {
-                    MazeAxis3::XY if mz.0.get((x + 1, y)).unwrap().is_traversable() => {
-                        self.player_pos.0 += 1
-                    }
-                    MazeAxis3::XZ if mz.0.get((x + 1, z)).unwrap().is_traversable() => {
-                        self.player_pos.0 += 1
-                    }
-                    MazeAxis3::YZ if mz.0.get((y + 1, z)).unwrap().is_traversable() => {
-                        self.player_pos.1 += 1
-                    }
-                    _ => {}
-                },
-                Key::Up => match self.cut_axis {
-                    MazeAxis3::XY if mz.0.get((x, y - 1)).unwrap().is_traversable() => {
-                        self.player_pos.1 -= 1
-                    }
-                    MazeAxis3::XZ if mz.0.get((x, z - 1)).unwrap().is_traversable() => {
-                        self.player_pos.2 -= 1
-                    }
-                    MazeAxis3::YZ if mz.0.get((y, z - 1)).unwrap().is_traversable() => {
-                        self.player_pos.2 -= 1
-                    }
-                    _ => {}
-                },
-                Key::Down => match self.cut_axis {
-                    MazeAxis3::XY if mz.0.get((x, y + 1)).unwrap().is_traversable() => {
-                        self.player_pos.1 += 1
-                    }
-                    MazeAxis3::XZ if mz.0.get((x, z + 1)).unwrap().is_traversable() => {
-                        self.player_pos.2 += 1
-                    }
-                    MazeAxis3::YZ if mz.0.get((y, z + 1)).unwrap().is_traversable() => {
-                        self.player_pos.2 += 1
-                    }
-                    _ => {}
-                },
-                // Rotation
-                Key::D => self.cut_axis.cycle(),
-                _ => {}
+            if let Some(action) = self.keyboard.key_press(key) {
+                self.state.apply(action);
             }
         };
+
+        // The gamepad and mouse still follow what is held down, but only the
+        // keyboard works on the rebinding screen
+        let gamepad = self.gamepad.event(e);
+        let mouse = self.mouse.event(e);
+        if self.keyboard.is_rebinding() {
+            return;
+        }
+
+        if let Some(action) = gamepad {
+            self.state.apply(action);
+        }
+
+        match mouse {
+            Some(MouseInput::Click(point)) => {
+                if let Some(cell) = self.cell_at(point) {
+                    self.state.walk_to(cell);
+                }
+            }
+            Some(MouseInput::Act(action)) => self.state.apply(action),
+            None => {}
+        }
     }
 
-    pub fn check_win(&mut self) {
-        if self.base_data.current_maze.0.get(self.player_pos) == Some(&MazeCell::End) {
-            // `base_data` changes
-            self.base_data.score += 1;
-            self.base_data.current_maze = gen_maze();
-            self.base_data.end_pos = self.base_data.current_maze.end_pos();
+    /// The cell of the slice drawn at `point` on the screen. This undoes the
+    /// scaling and offset that `draw` uses
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    pub fn cell_at(&self, point: [f64; 2]) -> Option<(usize, usize)> {
+        let slice = self.state.create_slice();
+        let scale = scale_factor(&slice);
+        let (rows, columns) = slice.0.dim();
+        let x = ((point[0] - OFFSET_FACTOR) / scale).floor();
+        let y = ((point[1] - OFFSET_FACTOR) / scale).floor();
+        if x < 0.0 || y < 0.0 {
+            return None;
+        }
 
-            // Reset `self`
-            self.cut_axis = MazeAxis3::XY;
-            self.player_pos = (1, 1, 1);
-        };
+        let (x, y) = (x as usize, y as usize);
+        if x < rows && y < columns {
+            Some((x, y))
+        } else {
+            None
+        }
     }
 
-    pub fn create_slice(&self) -> MazeSlice {
-        self.base_data.current_maze.view_2_axis(
-            self.cut_axis.clone(),
-            pick_axis(&self.cut_axis, self.player_pos),
-        )
+    /// Moves the game on by `dt` seconds and repeats the moves held down on
+    /// the gamepad. This is called with the `dt` of every update event
+    pub fn update(&mut self, dt: f64) {
+        self.state.update(dt);
+        for action in self.gamepad.update(dt) {
+            if !self.keyboard.is_rebinding() {
+                self.state.apply(action);
+            }
+        }
+    }
+
+    /// Records the game in the high scores and saves it, ready for the window
+    /// to close. Returns what happened for the player to be told
+    pub fn quit(&mut self) -> Vec<String> {
+        self.state.quit()
     }
 
-    pub fn draw_text<G: Graphics, C>(&self, glyphs: &mut C, c: &Context, g: &mut G)
+    pub fn draw_text<G: Graphics, C>(&self, view: &View, glyphs: &mut C, c: &Context, g: &mut G)
     where
         C: CharacterCache<Texture = G::Texture>,
     {
-        let current_pos_text = (
-            format!("Current position: {:?}", self.player_pos),
-            [10.0, 25.0],
-        );
-        let start_pos_text = ("Start position: (1, 1, 1)".to_string(), [260.0, 50.0]);
-        let end_pos_text = (
-            format!("End position: {:?}", self.base_data.end_pos),
-            [10.0, 75.0],
-        );
-        let current_axis_text = (
-            format!(
-                "Current Axis: {}",
-                match self.cut_axis {
-                    MazeAxis3::XY => "XY",
-                    MazeAxis3::XZ => "XZ",
-                    MazeAxis3::YZ => "YZ",
-                }
-                .to_string()
-            ),
-            [260.0, 100.0],
-        );
-        let score_text = (format!("Score: {}", self.base_data.score), [10.0, 125.0]);
-
-        let texts = [
-            current_pos_text,
-            start_pos_text,
-            end_pos_text,
-            current_axis_text,
-            score_text,
-        ];
-
-        let text_image = Image::new_color(colours::RED);
-
-        for (text, screen_pos) in &texts {
-            for (ch, text_pos) in text.chars().zip((0_u32..).map(f64::from)) {
-                if let Ok(glyph_char) = glyphs.character(20, ch) {
-                    text_image.draw(
-                        glyph_char.texture,
-                        &c.draw_state,
-                        c.transform.trans(
-                            screen_pos[0] + glyph_char.left() + text_pos * 20.0,
-                            /* The y-axis in font co-ordinates points up instead of down */
-                            screen_pos[1] - glyph_char.top(),
-                        ),
-                        g,
-                    )
-                }
+        draw_lines(&view.hud, glyphs, c, g);
+    }
+}
+
+/// The size of one cell on screen. This is `SCALE_FACTOR` unless the slice
+/// is too big to fit in `DRAW_AREA`
+#[allow(clippy::cast_possible_truncation)]
+pub fn scale_factor(slice: &MazeSlice) -> f64 {
+    let (rows, columns) = slice.0.dim();
+    SCALE_FACTOR.min(DRAW_AREA / f64::from(rows.max(columns) as u32))
+}
+
+/// The colour each kind of cell is drawn in
+pub fn cell_colour(cell: &MazeCell) -> Colour {
+    match cell {
+        MazeCell::Cell => colours::BLACK,
+        MazeCell::Wall => colours::BLUE,
+        MazeCell::OuterWall => colours::GREEN,
+        MazeCell::Start => colours::PINK,
+        MazeCell::End => colours::RED,
+        _ => unreachable!(),
+    }
+}
+
+/// The triangle marking an opening from the cell drawn at `rect` and its
+/// colour. `depth` is whether the opening is along the depth axis and
+/// `positive` whether it goes up the axis
+pub fn cue_triangle(rect: [f64; 4], depth: bool, positive: bool) -> ([[f64; 2]; 3], Colour) {
+    let [x_pos, y_pos, scale, _] = rect;
+    let (left, colour) = if depth {
+        (x_pos, colours::PURPLE)
+    } else {
+        (x_pos + scale / 2.0, colours::CYAN)
+    };
+    let (tip, base) = if positive {
+        (y_pos, y_pos + scale / 3.0)
+    } else {
+        (y_pos + scale, y_pos + scale * 2.0 / 3.0)
+    };
+
+    (
+        [
+            [left + scale / 4.0, tip],
+            [left, base],
+            [left + scale / 2.0, base],
+        ],
+        colour,
+    )
+}
+
+/// Draws each line of text at its position on the screen
+pub fn draw_lines<G: Graphics, C>(
+    texts: &[(String, [f64; 2])],
+    glyphs: &mut C,
+    c: &Context,
+    g: &mut G,
+) where
+    C: CharacterCache<Texture = G::Texture>,
+{
+    let text_image = Image::new_color(colours::RED);
+
+    for (text, screen_pos) in texts {
+        for (ch, text_pos) in text.chars().zip((0_u32..).map(f64::from)) {
+            if let Ok(glyph_char) = glyphs.character(20, ch) {
+                text_image.draw(
+                    glyph_char.texture,
+                    &c.draw_state,
+                    c.transform.trans(
+                        screen_pos[0] + glyph_char.left() + text_pos * 20.0,
+                        /* The y-axis in font co-ordinates points up instead of down */
+                        screen_pos[1] - glyph_char.top(),
+                    ),
+                    g,
+                )
             }
         }
     }
 }
 
-impl Default for BaseData {
-    #[inline]
-    fn default() -> Self {
-        let current_maze = gen_maze();
-        let end_pos = current_maze.end_pos();
-        Self {
-            current_maze,
-            end_pos,
-            score: 0,
+impl GameSettings {
+    /// The maze for `level` (counting from 0). Levels come from `pack` until
+    /// it runs out and are generated from `seed` after that
+    pub fn level_maze(&self, level: usize, seed: u64) -> Maze {
+        match self.pack_level(level) {
+            Some(saved) => saved.maze.clone(),
+            None => self.gen_maze(seed),
         }
     }
+
+    /// The level from `pack` for `level`, if there is one
+    pub fn pack_level(&self, level: usize) -> Option<&SavedMaze> {
+        self.pack.as_ref().and_then(|pack| pack.levels.get(level))
+    }
+
+    /// The mode and size of maze this game's scores are recorded under
+    pub fn board(&self) -> (MazeMode, MazePos) {
+        (self.mode, self.mode.shape(self.shape))
+    }
+
+    /// Generates a maze from `seed` with these settings
+    pub fn gen_maze(&self, seed: u64) -> Maze {
+        gen_maze_with_options(
+            &self.algorithm,
+            self.mode.shape(self.shape),
+            &self.options,
+            &mut seeded_rng(seed),
+        )
+    }
 }
 
-impl Default for CurrentData {
-    #[inline]
+impl Default for GameSettings {
     fn default() -> Self {
-        let base_data = BaseData::default();
-
         Self {
-            player_pos: (1, 1, 1),
-            cut_axis: MazeAxis3::XY,
-            base_data,
+            mode: MazeMode::FourD,
+            shape: MazeMode::FourD.default_shape(),
+            seed: None,
+            algorithm: Algorithm::default(),
+            options: MazeOptions {
+                start: StartPlacement::Corner,
+                exit: ExitPlacement::Farthest,
+                braid: 0,
+            },
+            pack: None,
+            save_path: PathBuf::from(DEFAULT_SAVE_FILE),
+            scores_path: PathBuf::from(DEFAULT_SCORES_FILE),
+            player_name: "Player".to_string(),
+            time_attack: None,
         }
     }
 }
 
-pub fn pick_axis(axis: &MazeAxis3, pos: (usize, usize, usize)) -> usize {
-    match axis {
-        MazeAxis3::XY => pos.2,
-        MazeAxis3::XZ => pos.1,
-        MazeAxis3::YZ => pos.0,
+impl Default for CurrentData {
+    #[inline]
+    fn default() -> Self {
+        let settings = GameSettings::default();
+        let high_scores = HighScores::load(&settings.scores_path);
+        Self::new(GameState::new(settings, high_scores))
     }
 }
 
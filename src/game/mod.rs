@@ -1,7 +1,106 @@
+#[cfg(feature = "window")]
 pub mod piston;
 pub mod maze_controller;
+pub mod game_state;
 pub mod events;
+pub mod save;
+pub mod high_scores;
+pub mod controls;
+pub mod gamepad;
+pub mod mouse;
+pub mod hint;
+pub mod history;
+pub mod timer;
+pub mod terminal;
+#[cfg(feature = "window")]
+pub mod ecs;
+pub mod render;
+#[cfg(feature = "window")]
+pub mod window;
 
-pub fn run() {
-    piston::run()
+use self::{
+    game_state::GameState,
+    high_scores::HighScores,
+    maze_controller::GameSettings,
+    save::SaveGame,
+};
+use std::{fmt, str::FromStr};
+
+/// How the game is shown and played
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Frontend {
+    /// A window drawn with OpenGL. This and `Ecs` need the `window` feature,
+    /// which links SDL2
+    Piston,
+    /// The terminal, which needs no window so it works over SSH
+    Terminal,
+    /// A window drawn from an entity component system, which looks however
+    /// `resources/display_config.ron` says
+    Ecs,
+}
+
+/// Starts a new game
+pub fn run(settings: GameSettings, frontend: Frontend) {
+    let high_scores = HighScores::load(&settings.scores_path);
+    play(GameState::new(settings, high_scores), frontend)
+}
+
+/// Carries on a saved game
+pub fn continue_game(save: SaveGame, frontend: Frontend) {
+    let high_scores = HighScores::load(&save.settings.scores_path);
+    play(GameState::from_save(save, high_scores), frontend)
+}
+
+fn play(state: GameState, frontend: Frontend) {
+    match frontend {
+        #[cfg(feature = "window")]
+        Frontend::Piston => piston::run(maze_controller::CurrentData::new(state)),
+        Frontend::Terminal => {
+            if let Err(error) = terminal::run(state) {
+                eprintln!("The terminal could not be used: {}", error);
+            }
+        }
+        #[cfg(feature = "window")]
+        Frontend::Ecs => ecs::run(state),
+        #[cfg(not(feature = "window"))]
+        Frontend::Piston | Frontend::Ecs => eprintln!(
+            "The {} frontend needs a window, which this build of the game leaves out. \
+             Use --frontend terminal or build it with the window feature",
+            frontend
+        ),
+    }
+}
+
+/// The window when there is one, otherwise the terminal
+impl Default for Frontend {
+    fn default() -> Self {
+        if cfg!(feature = "window") {
+            Frontend::Piston
+        } else {
+            Frontend::Terminal
+        }
+    }
+}
+
+impl fmt::Display for Frontend {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Frontend::Piston => write!(f, "piston"),
+            Frontend::Terminal => write!(f, "terminal"),
+            Frontend::Ecs => write!(f, "ecs"),
+        }
+    }
+}
+
+impl FromStr for Frontend {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name {
+            "piston" => Ok(Frontend::Piston),
+            "terminal" => Ok(Frontend::Terminal),
+            "ecs" => Ok(Frontend::Ecs),
+            _ => Err(format!("{:?} is not a frontend", name)),
+        }
+    }
 }
@@ -0,0 +1,78 @@
+//! Playing with the mouse. Clicking a cell of the slice walks the player
+//! there (`GameState::walk_to`) along the quickest path that stays on the
+//! slice. The right button and the scroll wheel turn onto another plane
+use super::controls::Action;
+use piston::input::{Button, GenericEvent, MouseButton};
+
+/// Where the cursor is
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Mouse {
+    cursor: [f64; 2],
+}
+
+/// What the player did with the mouse
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum MouseInput {
+    /// The left button was clicked at this point on the screen
+    Click([f64; 2]),
+    /// Something to do straight away
+    Act(Action),
+}
+
+impl Mouse {
+    /// Keeps track of the cursor and turns clicks and scrolling into input
+    pub fn event<E: GenericEvent>(&mut self, e: &E) -> Option<MouseInput> {
+        if let Some(cursor) = e.mouse_cursor_args() {
+            self.cursor = cursor;
+        }
+
+        match e.press_args() {
+            Some(Button::Mouse(MouseButton::Left)) => return Some(MouseInput::Click(self.cursor)),
+            Some(Button::Mouse(MouseButton::Right)) => {
+                return Some(MouseInput::Act(Action::RotateForward))
+            }
+            _ => {}
+        }
+
+        // Scrolling up turns forward and down turns back
+        match e.mouse_scroll_args() {
+            Some([_, y]) if y > 0.0 => Some(MouseInput::Act(Action::RotateForward)),
+            Some([_, y]) if y < 0.0 => Some(MouseInput::Act(Action::RotateBack)),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use piston::input::{ButtonArgs, ButtonState, Event, Input, Motion};
+
+    fn press(button: MouseButton) -> Event {
+        Event::Input(Input::Button(ButtonArgs {
+            state: ButtonState::Press,
+            button: Button::Mouse(button),
+            scancode: None,
+        }))
+    }
+
+    #[test]
+    fn clicks_and_scrolling() {
+        let mut mouse = Mouse::default();
+        let cursor = Event::Input(Input::Move(Motion::MouseCursor(200.0, 300.5)));
+        assert_eq!(mouse.event(&cursor), None);
+        assert_eq!(
+            mouse.event(&press(MouseButton::Left)),
+            Some(MouseInput::Click([200.0, 300.5]))
+        );
+        assert_eq!(
+            mouse.event(&press(MouseButton::Right)),
+            Some(MouseInput::Act(Action::RotateForward))
+        );
+        let scroll = Event::Input(Input::Move(Motion::MouseScroll(0.0, -1.0)));
+        assert_eq!(
+            mouse.event(&scroll),
+            Some(MouseInput::Act(Action::RotateBack))
+        );
+    }
+}
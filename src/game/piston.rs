@@ -1,40 +1,31 @@
-use super::maze_controller::CurrentData;
-use glutin_window::GlutinWindow;
-use opengl_graphics::{Filter, GlGraphics, GlyphCache, OpenGL, TextureSettings};
-use piston::{
-    event_loop::{EventLoop, EventSettings, Events},
-    input::RenderEvent,
-    window::WindowSettings,
+use super::{
+    maze_controller::CurrentData,
+    window::{self, WindowFrontend},
 };
+use graphics::Context;
+use opengl_graphics::{GlGraphics, GlyphCache};
+use piston::{input::Event, window::WindowSettings};
 
-pub fn run() {
+pub fn run(mut current_data: CurrentData) {
     // This initialises the basic window
     let settings = WindowSettings::new("4D maze", [900; 2]);
-    let mut window: GlutinWindow = settings.build().expect("Window Creation failed");
-    let mut events = Events::new(EventSettings::new().lazy(true));
-
-    // Here we set up OpenGL
-    let opengl = OpenGL::V3_2;
-    let mut gl = GlGraphics::new(opengl);
-
-    // Set up maze
-    let mut current_data = CurrentData::new();
+    window::run(&settings, [1.0; 4], &mut current_data);
+}
 
-    let texture_settings = TextureSettings::new().filter(Filter::Nearest);
-    let glyphs = &mut GlyphCache::new("assets/Games.ttf", (), texture_settings)
-        .expect("Could not load font");
+impl WindowFrontend for CurrentData {
+    fn event(&mut self, e: &Event) {
+        self.events(e);
+    }
 
-    while let Some(e) = events.next(&mut window) {
-        current_data.events(&e);
-        current_data.check_win();
+    fn update(&mut self, dt: f64) {
+        CurrentData::update(self, dt);
+    }
 
-        if let Some(args) = e.render_args() {
-            gl.draw(args.viewport(), |c, g| {
-                use graphics::clear;
+    fn quit(&mut self) -> Vec<String> {
+        CurrentData::quit(self)
+    }
 
-                clear([1.0; 4], g);
-                current_data.draw(glyphs, &c, g)
-            });
-        }
+    fn draw(&self, glyphs: &mut GlyphCache, c: &Context, g: &mut GlGraphics) {
+        CurrentData::draw(self, glyphs, c, g);
     }
 }
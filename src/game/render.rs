@@ -0,0 +1,454 @@
+//! Drawing the game into an image instead of a window, so it works on
+//! computers with no screen or GPU. The slice is drawn with the same colours
+//! and layout as `CurrentData::draw`, and images can be saved as PNGs for
+//! level thumbnails and screenshots.
+//!
+//! Every pixel is either inside a shape or not, going by its centre, so the
+//! same picture always comes out exactly the same and tests can compare
+//! pixels. Only text is blended into the background
+use super::{
+    controls::{Keyboard, DEFAULT_CONTROLS_FILE},
+    game_state::{GameState, View},
+    high_scores::HighScores,
+    maze_controller::{
+        cell_colour, colours, cue_triangle, scale_factor, GameSettings, OFFSET_FACTOR,
+    },
+};
+use crate::MazeSlice;
+use graphics::types::Color as Colour;
+use rusttype::{point, Font, Scale};
+use std::{
+    fs::{self, File},
+    io::{self, BufWriter, Write},
+    path::Path,
+};
+
+/// The width and height of the window, which `render_view` draws the same
+pub const SCREEN_SIZE: u32 = 900;
+
+/// The height of text in pixels
+pub const FONT_SIZE: f32 = 20.0;
+
+/// How far apart the letters of a line are, like `draw_lines` in the window
+const LETTER_SPACING: f32 = 20.0;
+
+/// The font the HUD is written in
+pub const DEFAULT_FONT_FILE: &str = "assets/Games.ttf";
+
+/// An image being drawn on. Pixels are stored a row at a time as red, green,
+/// blue and alpha bytes
+#[derive(Clone, Debug, PartialEq)]
+pub struct Canvas {
+    width: u32,
+    height: u32,
+    pixels: Vec<u8>,
+}
+
+impl Canvas {
+    /// A `width` by `height` image filled with `background`
+    pub fn new(width: u32, height: u32, background: Colour) -> Self {
+        let colour = to_bytes(background);
+        let pixels = (0..width * height).flat_map(|_| colour.to_vec()).collect();
+        Self {
+            width,
+            height,
+            pixels,
+        }
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// The bytes of every pixel, a row at a time
+    pub fn pixels(&self) -> &[u8] {
+        &self.pixels
+    }
+
+    /// The colour of the pixel at `x`, `y`
+    pub fn pixel(&self, x: u32, y: u32) -> [u8; 4] {
+        let index = self.index(x, y);
+        let mut colour = [0; 4];
+        colour.copy_from_slice(&self.pixels[index..index + 4]);
+        colour
+    }
+
+    /// Fills the rectangle `[x, y, width, height]`
+    pub fn fill_rect(&mut self, rect: [f64; 4], colour: Colour) {
+        let [x, y, width, height] = rect;
+        self.fill_where(rect, colour, |px, py| {
+            px >= x && px < x + width && py >= y && py < y + height
+        });
+    }
+
+    /// Fills the ellipse that touches the sides of `rect`
+    pub fn fill_ellipse(&mut self, rect: [f64; 4], colour: Colour) {
+        let [x, y, width, height] = rect;
+        let (rx, ry) = (width / 2.0, height / 2.0);
+        let (cx, cy) = (x + rx, y + ry);
+        self.fill_where(rect, colour, |px, py| {
+            let (dx, dy) = ((px - cx) / rx, (py - cy) / ry);
+            dx * dx + dy * dy <= 1.0
+        });
+    }
+
+    /// Fills the polygon with corners at `points`
+    pub fn fill_polygon(&mut self, points: &[[f64; 2]], colour: Colour) {
+        if points.is_empty() {
+            return;
+        }
+        let (mut left, mut top) = (points[0][0], points[0][1]);
+        let (mut right, mut bottom) = (left, top);
+        for &[x, y] in points {
+            left = left.min(x);
+            right = right.max(x);
+            top = top.min(y);
+            bottom = bottom.max(y);
+        }
+
+        self.fill_where([left, top, right - left, bottom - top], colour, |px, py| {
+            // A point is inside if a line going right from it crosses the
+            // edges an odd number of times
+            let mut inside = false;
+            let mut previous = points[points.len() - 1];
+            for &point in points {
+                let ([x1, y1], [x2, y2]) = (previous, point);
+                if (y1 > py) != (y2 > py) && px < x1 + (py - y1) * (x2 - x1) / (y2 - y1) {
+                    inside = !inside;
+                }
+                previous = point;
+            }
+            inside
+        });
+    }
+
+    /// Writes `text` with its baseline starting at `pos`, putting every
+    /// letter `LETTER_SPACING` apart like the window does
+    #[allow(
+        clippy::cast_possible_truncation,
+        clippy::cast_precision_loss,
+        clippy::cast_possible_wrap
+    )]
+    pub fn draw_text(&mut self, font: &Font, text: &str, pos: [f64; 2], colour: Colour) {
+        let scale = Scale::uniform(FONT_SIZE);
+        for (index, ch) in text.chars().enumerate() {
+            let start = point(pos[0] as f32 + index as f32 * LETTER_SPACING, pos[1] as f32);
+            let glyph = font.glyph(ch).scaled(scale).positioned(start);
+            if let Some(bounds) = glyph.pixel_bounding_box() {
+                glyph.draw(|x, y, coverage| {
+                    let (x, y) = (bounds.min.x + x as i32, bounds.min.y + y as i32);
+                    self.blend(x, y, colour, coverage);
+                });
+            }
+        }
+    }
+
+    /// Encodes the image as a PNG
+    pub fn write_png<W: Write>(&self, writer: W) -> Result<(), png::EncodingError> {
+        use png::HasParameters;
+
+        let mut encoder = png::Encoder::new(writer, self.width, self.height);
+        encoder.set(png::ColorType::RGBA).set(png::BitDepth::Eight);
+        encoder.write_header()?.write_image_data(&self.pixels)
+    }
+
+    /// Saves the image as a PNG at `path`
+    pub fn save_png<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        let file = BufWriter::new(File::create(path)?);
+        self.write_png(file).map_err(|error| match error {
+            png::EncodingError::IoError(error) => error,
+            png::EncodingError::Format(message) => {
+                io::Error::new(io::ErrorKind::InvalidData, message)
+            }
+        })
+    }
+
+    /// Sets every pixel in `bounds` whose centre `inside` says is in the shape
+    #[allow(
+        clippy::cast_possible_truncation,
+        clippy::cast_sign_loss,
+        clippy::cast_precision_loss
+    )]
+    fn fill_where<F: Fn(f64, f64) -> bool>(&mut self, bounds: [f64; 4], colour: Colour, inside: F) {
+        let colour = to_bytes(colour);
+        let [x, y, width, height] = bounds;
+        let first_x = x.floor().max(0.0) as u32;
+        let first_y = y.floor().max(0.0) as u32;
+        let last_x = ((x + width).ceil().max(0.0) as u32).min(self.width);
+        let last_y = ((y + height).ceil().max(0.0) as u32).min(self.height);
+
+        for py in first_y..last_y {
+            for px in first_x..last_x {
+                if inside(f64::from(px) + 0.5, f64::from(py) + 0.5) {
+                    let index = self.index(px, py);
+                    self.pixels[index..index + 4].copy_from_slice(&colour);
+                }
+            }
+        }
+    }
+
+    /// Mixes `coverage` (from 0 to 1) of `colour` into the pixel at `x`, `y`
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    fn blend(&mut self, x: i32, y: i32, colour: Colour, coverage: f32) {
+        if x < 0 || y < 0 || x as u32 >= self.width || y as u32 >= self.height {
+            return;
+        }
+        let index = self.index(x as u32, y as u32);
+        let colour = to_bytes(colour);
+        let coverage = coverage.max(0.0).min(1.0);
+        for (old, new) in self.pixels[index..index + 3].iter_mut().zip(&colour) {
+            let mixed = f32::from(*old) * (1.0 - coverage) + f32::from(*new) * coverage;
+            *old = mixed.round() as u8;
+        }
+    }
+
+    fn index(&self, x: u32, y: u32) -> usize {
+        ((y * self.width + x) * 4) as usize
+    }
+}
+
+/// Loads the font at `path` to write text with
+pub fn load_font<P: AsRef<Path>>(path: P) -> io::Result<Font<'static>> {
+    font_from_bytes(fs::read(path)?)
+}
+
+/// Reads a TrueType font
+pub fn font_from_bytes(bytes: Vec<u8>) -> io::Result<Font<'static>> {
+    Font::from_bytes(bytes).map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))
+}
+
+/// Draws the first maze of a new game with `settings` as the window would
+/// show it, and saves it as a PNG at `path`. The HUD is left out if the font
+/// can't be loaded
+pub fn save_first_maze<P: AsRef<Path>>(settings: GameSettings, path: P) -> io::Result<()> {
+    let state = GameState::new(settings, HighScores::default());
+    let keyboard = Keyboard::load(DEFAULT_CONTROLS_FILE);
+    let font = load_font(DEFAULT_FONT_FILE).ok();
+    render_view(&state.view(keyboard.controls()), font.as_ref()).save_png(path)
+}
+
+/// Draws `slice` on its own with each cell `cell_size` pixels across, and the
+/// player at `player` if it is given. The image is exactly as big as the
+/// slice, which suits thumbnails
+#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+pub fn render_slice(slice: &MazeSlice, player: Option<(usize, usize)>, cell_size: u32) -> Canvas {
+    let (rows, columns) = slice.0.dim();
+    let mut canvas = Canvas::new(
+        rows as u32 * cell_size,
+        columns as u32 * cell_size,
+        [1.0; 4],
+    );
+    let scale = f64::from(cell_size);
+    draw_cells(&mut canvas, slice, 0.0, scale);
+    if let Some(player) = player {
+        canvas.fill_ellipse(cell_rect(player, 0.0, scale), colours::YELLOW);
+    }
+    canvas
+}
+
+/// Draws everything the window would show for `view`, at the size of the
+/// window. Text is only written if a `font` is given
+pub fn render_view(view: &View, font: Option<&Font>) -> Canvas {
+    let mut canvas = Canvas::new(SCREEN_SIZE, SCREEN_SIZE, [1.0; 4]);
+    let texts = match &view.overlay {
+        Some(texts) => texts,
+        None => {
+            let scale = scale_factor(&view.slice);
+            draw_cells(&mut canvas, &view.slice, OFFSET_FACTOR, scale);
+
+            let (first, _) = view.plane.hidden_axes();
+            for &(cell, dir) in &view.depth_cues {
+                let rect = cell_rect(cell, OFFSET_FACTOR, scale);
+                let (triangle, colour) = cue_triangle(rect, dir.axis == first, dir.positive);
+                canvas.fill_polygon(&triangle, colour);
+            }
+            for &cell in &view.hint {
+                let [x, y, width, height] = cell_rect(cell, OFFSET_FACTOR, scale);
+                canvas.fill_rect(
+                    [x + width / 4.0, y + height / 4.0, width / 2.0, height / 2.0],
+                    colours::ORANGE,
+                );
+            }
+            canvas.fill_ellipse(
+                cell_rect(view.player, OFFSET_FACTOR, scale),
+                colours::YELLOW,
+            );
+            &view.hud
+        }
+    };
+
+    if let Some(font) = font {
+        for (text, pos) in texts {
+            canvas.draw_text(font, text, *pos, colours::RED);
+        }
+    }
+    canvas
+}
+
+/// Draws every cell of `slice`
+fn draw_cells(canvas: &mut Canvas, slice: &MazeSlice, offset: f64, scale: f64) {
+    for (cell, kind) in slice.0.indexed_iter() {
+        canvas.fill_rect(cell_rect(cell, offset, scale), cell_colour(kind));
+    }
+}
+
+/// Where the cell at `cell` is drawn
+#[allow(clippy::cast_possible_truncation)]
+fn cell_rect(cell: (usize, usize), offset: f64, scale: f64) -> [f64; 4] {
+    [
+        f64::from(cell.0 as u32) * scale + offset,
+        f64::from(cell.1 as u32) * scale + offset,
+        scale,
+        scale,
+    ]
+}
+
+/// A colour as bytes
+#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+fn to_bytes(colour: Colour) -> [u8; 4] {
+    let mut bytes = [0; 4];
+    for (byte, part) in bytes.iter_mut().zip(&colour) {
+        *byte = (part.max(0.0).min(1.0) * 255.0).round() as u8;
+    }
+    bytes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Direction, Maze, MazeAxis4};
+
+    const WHITE: [u8; 4] = [255; 4];
+    const BLUE: [u8; 4] = [0, 0, 255, 255];
+    const YELLOW: [u8; 4] = [255, 255, 0, 255];
+
+    #[test]
+    fn shapes() {
+        let mut canvas = Canvas::new(10, 10, [1.0; 4]);
+        canvas.fill_rect([2.0, 2.0, 3.0, 2.0], colours::BLUE);
+        assert_eq!(canvas.pixel(2, 2), BLUE);
+        assert_eq!(canvas.pixel(4, 3), BLUE);
+        assert_eq!(canvas.pixel(5, 3), WHITE);
+        assert_eq!(canvas.pixel(4, 4), WHITE);
+
+        canvas.fill_ellipse([0.0, 0.0, 10.0, 10.0], colours::YELLOW);
+        assert_eq!(canvas.pixel(5, 5), YELLOW);
+        assert_eq!(canvas.pixel(0, 0), WHITE);
+        assert_eq!(canvas.pixel(9, 9), WHITE);
+
+        let (triangle, _) = cue_triangle([0.0, 0.0, 8.0, 8.0], true, true);
+        let mut canvas = Canvas::new(8, 8, [1.0; 4]);
+        canvas.fill_polygon(&triangle, colours::PURPLE);
+        assert_eq!(canvas.pixel(1, 2), to_bytes(colours::PURPLE));
+        assert_eq!(canvas.pixel(0, 0), WHITE);
+        assert_eq!(canvas.pixel(1, 5), WHITE);
+
+        // Shapes off the edge are cut off
+        canvas.fill_rect([-5.0, 6.0, 100.0, 100.0], colours::BLUE);
+        assert_eq!(canvas.pixel(7, 7), BLUE);
+    }
+
+    #[test]
+    fn png_round_trip() {
+        let mut canvas = Canvas::new(4, 3, [1.0; 4]);
+        canvas.fill_rect([1.0, 1.0, 2.0, 1.0], colours::RED);
+        let mut bytes = Vec::new();
+        canvas.write_png(&mut bytes).unwrap();
+
+        let (info, mut reader) = png::Decoder::new(&bytes[..]).read_info().unwrap();
+        assert_eq!((info.width, info.height), (4, 3));
+        let mut pixels = vec![0; info.buffer_size()];
+        reader.next_frame(&mut pixels).unwrap();
+        assert_eq!(pixels, canvas.pixels());
+    }
+
+    #[test]
+    fn first_maze_png() {
+        let path = std::env::temp_dir().join(format!("fourD_maze_{}.png", std::process::id()));
+        let mut settings = GameSettings::default();
+        settings.seed = Some(1);
+        save_first_maze(settings, &path).unwrap();
+
+        let bytes = fs::read(&path).unwrap();
+        let (info, _) = png::Decoder::new(&bytes[..]).read_info().unwrap();
+        assert_eq!((info.width, info.height), (SCREEN_SIZE, SCREEN_SIZE));
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn slice_thumbnail() {
+        let maze = Maze::corridor();
+        let slice = maze.view_2_axis(MazeAxis4::XY, (1, 1, 1, 1));
+        let canvas = render_slice(&slice, Some((3, 1)), 4);
+        assert_eq!((canvas.width(), canvas.height()), (28, 12));
+
+        // The middle of each cell
+        let centre = |x: u32, y: u32| canvas.pixel(x * 4 + 2, y * 4 + 2);
+        assert_eq!(centre(0, 0), BLUE);
+        assert_eq!(centre(1, 1), to_bytes(colours::PINK));
+        assert_eq!(centre(2, 1), WHITE);
+        assert_eq!(centre(3, 1), YELLOW);
+        assert_eq!(centre(5, 1), to_bytes(colours::RED));
+        // The corners of the player's cell aren't covered
+        assert_eq!(canvas.pixel(12, 4), WHITE);
+    }
+
+    #[test]
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    fn whole_screen() {
+        let maze = Maze::corridor();
+        let font = font_from_bytes(include_bytes!("../../assets/Games.ttf").to_vec()).unwrap();
+        let mut view = View {
+            slice: maze.view_2_axis(MazeAxis4::XY, (1, 1, 1, 1)),
+            plane: MazeAxis4::XY,
+            player: (1, 1),
+            hint: vec![(2, 1)],
+            depth_cues: vec![(
+                (4, 1),
+                Direction {
+                    axis: 2,
+                    positive: true,
+                },
+            )],
+            hud: vec![("Level: 1".to_string(), [10.0, 25.0])],
+            overlay: None,
+        };
+
+        let canvas = render_view(&view, Some(&font));
+        assert_eq!(canvas.width(), SCREEN_SIZE);
+        let scale = scale_factor(&view.slice);
+        let centre = |canvas: &Canvas, x: f64, y: f64| {
+            let rect = cell_rect((0, 0), OFFSET_FACTOR, scale);
+            canvas.pixel(
+                (rect[0] + scale * (x + 0.5)) as u32,
+                (rect[1] + scale * (y + 0.5)) as u32,
+            )
+        };
+        assert_eq!(centre(&canvas, 0.0, 0.0), BLUE);
+        assert_eq!(centre(&canvas, 1.0, 1.0), YELLOW);
+        assert_eq!(centre(&canvas, 2.0, 1.0), to_bytes(colours::ORANGE));
+        // The depth cue is in the top left of its cell
+        assert_eq!(
+            centre(&canvas, 4.0 - 0.25, 1.0 - 0.3),
+            to_bytes(colours::PURPLE)
+        );
+        // The HUD is written in the top left
+        let written =
+            |canvas: &Canvas| (0..200).any(|x| (0..60).any(|y| canvas.pixel(x, y) != WHITE));
+        assert!(written(&canvas));
+        assert!(!written(&render_view(&view, None)));
+
+        // The same view always looks the same
+        assert_eq!(render_view(&view, Some(&font)), canvas);
+
+        // An overlay covers the maze
+        view.overlay = Some(vec![("High scores".to_string(), [10.0, 50.0])]);
+        let canvas = render_view(&view, Some(&font));
+        assert_eq!(centre(&canvas, 0.0, 0.0), WHITE);
+        assert!(written(&canvas));
+    }
+}
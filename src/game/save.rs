@@ -0,0 +1,335 @@
+//! Saves the whole game so it can be continued later. A save is written in
+//! the same style as the binary maze format: it starts with `4DSG` and a
+//! version, then the settings the game was started with, the current maze and
+//! where the player is in it
+use super::{
+    maze_controller::GameSettings,
+    timer::{GameClock, TimeAttack},
+};
+use crate::maze_lib::maze_file::{malformed, write_pos, write_string, Reader};
+use crate::{LevelPack, Maze, MazeAxis4, MazeFileError, MazeMode, MazePos, SavedMaze};
+use std::{fs, path::Path};
+
+/// The version of save written by this code. Other versions are refused
+pub const SAVE_VERSION: u16 = 1;
+
+/// Where the game is saved when no other file is given
+pub const DEFAULT_SAVE_FILE: &str = "fourD_maze.save";
+
+const SAVE_MAGIC: &[u8; 4] = b"4DSG";
+
+/// Every plane in the order it is saved
+const AXES: [MazeAxis4; 6] = [
+    MazeAxis4::XY,
+    MazeAxis4::XZ,
+    MazeAxis4::XW,
+    MazeAxis4::YZ,
+    MazeAxis4::YW,
+    MazeAxis4::ZW,
+];
+
+/// Everything needed to carry on a game from where it was left
+#[derive(Clone, Debug, PartialEq)]
+pub struct SaveGame {
+    /// The settings the game was started with. `save_path` isn't saved, it is
+    /// always the file the game was loaded from, and neither is
+    /// `scores_path`
+    pub settings: GameSettings,
+    pub maze: Maze,
+    pub seed: u64,
+    pub first_seed: u64,
+    pub level: usize,
+    pub score: u64,
+    pub moves: u64,
+    /// The moves taken on the current maze
+    pub level_moves: u64,
+    pub undos: u64,
+    /// The hints used on the current maze
+    pub level_hints: u64,
+    pub clock: GameClock,
+    pub player_pos: MazePos,
+    pub cut_axis: MazeAxis4,
+}
+
+impl SaveGame {
+    /// Encodes the save
+    #[allow(clippy::cast_possible_truncation)]
+    pub fn to_bytes(&self) -> Result<Vec<u8>, MazeFileError> {
+        let settings = &self.settings;
+        let mut bytes = SAVE_MAGIC.to_vec();
+        bytes.extend_from_slice(&SAVE_VERSION.to_le_bytes());
+
+        bytes.push(match settings.mode {
+            MazeMode::ThreeD => 3,
+            MazeMode::FourD => 4,
+        });
+        write_pos(&mut bytes, settings.shape);
+        match settings.seed {
+            Some(seed) => {
+                bytes.push(1);
+                bytes.extend_from_slice(&seed.to_le_bytes());
+            }
+            None => bytes.push(0),
+        }
+        write_string(&mut bytes, &settings.algorithm.to_string())?;
+        write_string(&mut bytes, &settings.options.start.to_string())?;
+        write_string(&mut bytes, &settings.options.exit.to_string())?;
+        bytes.push(settings.options.braid);
+        write_string(&mut bytes, &settings.player_name)?;
+        match settings.time_attack {
+            Some(rules) => {
+                bytes.push(1);
+                write_time(&mut bytes, rules.budget);
+                write_time(&mut bytes, rules.bonus);
+            }
+            None => bytes.push(0),
+        }
+        let pack = match &settings.pack {
+            Some(pack) => pack.to_bytes()?,
+            None => Vec::new(),
+        };
+        bytes.extend_from_slice(&(pack.len() as u32).to_le_bytes());
+        bytes.extend_from_slice(&pack);
+
+        let maze = SavedMaze::new(self.maze.clone()).to_bytes()?;
+        bytes.extend_from_slice(&(maze.len() as u32).to_le_bytes());
+        bytes.extend_from_slice(&maze);
+        bytes.extend_from_slice(&self.seed.to_le_bytes());
+        bytes.extend_from_slice(&self.first_seed.to_le_bytes());
+        bytes.extend_from_slice(&(self.level as u32).to_le_bytes());
+        bytes.extend_from_slice(&self.score.to_le_bytes());
+        bytes.extend_from_slice(&self.moves.to_le_bytes());
+        bytes.extend_from_slice(&self.level_moves.to_le_bytes());
+        bytes.extend_from_slice(&self.undos.to_le_bytes());
+        bytes.extend_from_slice(&self.level_hints.to_le_bytes());
+
+        write_time(&mut bytes, self.clock.session_time());
+        write_time(&mut bytes, self.clock.level_time());
+        if let Some(rules) = settings.time_attack {
+            write_time(&mut bytes, self.clock.remaining().unwrap_or(rules.budget));
+        }
+
+        write_pos(&mut bytes, self.player_pos);
+        /* Every plane is in `AXES` */
+        bytes.push(AXES.iter().position(|&axis| axis == self.cut_axis).unwrap() as u8);
+
+        Ok(bytes)
+    }
+
+    /// Decodes a save. `save_path` is put in the settings so the game saves
+    /// back to the same file
+    pub fn from_bytes<P: AsRef<Path>>(bytes: &[u8], save_path: P) -> Result<Self, MazeFileError> {
+        let mut reader = Reader { bytes };
+
+        // When the format changes, older versions are read here and turned
+        // into the newest one
+        let save = match reader.version(SAVE_MAGIC)? {
+            SAVE_VERSION => read_save(&mut reader, save_path.as_ref())?,
+            version => return Err(MazeFileError::WrongVersion(version)),
+        };
+        if !reader.bytes.is_empty() {
+            return Err(malformed("there is data after the save"));
+        }
+
+        if save
+            .maze
+            .0
+            .get(save.player_pos)
+            .map_or(true, |cell| !cell.is_traversable())
+        {
+            return Err(malformed("the player is inside a wall"));
+        }
+        if save.settings.mode == MazeMode::ThreeD && save.cut_axis.visible_axes().1 == 3 {
+            return Err(malformed("a 3D maze can't be cut along W"));
+        }
+        Ok(save)
+    }
+
+    /// Loads the save in `path`
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, MazeFileError> {
+        Self::from_bytes(&fs::read(&path)?, path)
+    }
+
+    /// Writes the save to the file in its settings
+    pub fn save(&self) -> Result<(), MazeFileError> {
+        Ok(fs::write(&self.settings.save_path, self.to_bytes()?)?)
+    }
+}
+
+/// Reads a save of the current version
+fn read_save(reader: &mut Reader, save_path: &Path) -> Result<SaveGame, MazeFileError> {
+    let mode = match reader.u8()? {
+        3 => MazeMode::ThreeD,
+        4 => MazeMode::FourD,
+        mode => return Err(malformed(format!("{} is not a mode", mode))),
+    };
+    let shape = reader.pos()?;
+    let first_seed = match reader.u8()? {
+        0 => None,
+        1 => Some(reader.u64()?),
+        flag => return Err(malformed(format!("{} is not a seed flag", flag))),
+    };
+
+    let mut settings = GameSettings {
+        mode,
+        shape,
+        seed: first_seed,
+        save_path: save_path.to_path_buf(),
+        ..GameSettings::default()
+    };
+    settings.algorithm = reader.string()?.parse().map_err(MazeFileError::Malformed)?;
+    settings.options.start = reader.string()?.parse().map_err(MazeFileError::Malformed)?;
+    settings.options.exit = reader.string()?.parse().map_err(MazeFileError::Malformed)?;
+    settings.options.braid = reader.u8()?;
+    settings.player_name = reader.string()?;
+    settings.time_attack = match reader.u8()? {
+        0 => None,
+        1 => Some(TimeAttack {
+            budget: read_time(reader)?,
+            bonus: read_time(reader)?,
+        }),
+        flag => return Err(malformed(format!("{} is not a time-attack flag", flag))),
+    };
+    let pack_len = reader.u32()? as usize;
+    if pack_len > 0 {
+        settings.pack = Some(LevelPack::from_bytes(reader.take(pack_len)?)?);
+    }
+
+    let maze_len = reader.u32()? as usize;
+    let maze = SavedMaze::from_bytes(reader.take(maze_len)?)?.maze;
+    let seed = reader.u64()?;
+    let first_seed = reader.u64()?;
+    let level = reader.u32()? as usize;
+    let score = reader.u64()?;
+    let moves = reader.u64()?;
+    let level_moves = reader.u64()?;
+    let undos = reader.u64()?;
+    let level_hints = reader.u64()?;
+
+    let play_time = read_time(reader)?;
+    let level_time = read_time(reader)?;
+    let remaining = match settings.time_attack {
+        Some(_) => Some(read_time(reader)?),
+        None => None,
+    };
+    let clock = GameClock::resume(settings.time_attack, level_time, play_time, remaining);
+
+    let player_pos = reader.pos()?;
+    let cut_axis = *AXES
+        .get(usize::from(reader.u8()?))
+        .ok_or_else(|| malformed("the plane is not one of the 6 planes"))?;
+
+    Ok(SaveGame {
+        settings,
+        maze,
+        seed,
+        first_seed,
+        level,
+        score,
+        moves,
+        level_moves,
+        undos,
+        level_hints,
+        clock,
+        player_pos,
+        cut_axis,
+    })
+}
+
+fn write_time(bytes: &mut Vec<u8>, seconds: f64) {
+    bytes.extend_from_slice(&seconds.to_bits().to_le_bytes());
+}
+
+/// Reads a number of seconds, which can't be negative
+fn read_time(reader: &mut Reader) -> Result<f64, MazeFileError> {
+    let seconds = f64::from_bits(reader.u64()?);
+    if seconds.is_finite() && seconds >= 0.0 {
+        Ok(seconds)
+    } else {
+        Err(malformed(format!("{} is not a time", seconds)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{gen_maze_with_seed, Algorithm, ExitPlacement};
+
+    fn save_game() -> SaveGame {
+        let mut settings = GameSettings::default();
+        settings.seed = Some(12);
+        settings.algorithm = Algorithm::Eller;
+        settings.options.exit = ExitPlacement::Random { min_length: 9 };
+        settings.options.braid = 20;
+        settings.save_path = "test.save".into();
+        settings.time_attack = Some(TimeAttack {
+            budget: 60.0,
+            bonus: 10.0,
+        });
+
+        let clock = GameClock::resume(settings.time_attack, 4.25, 99.5, Some(31.0));
+        let maze = gen_maze_with_seed((9, 9, 7, 5), 77);
+        SaveGame {
+            settings,
+            player_pos: maze.start_pos(),
+            maze,
+            seed: 77,
+            first_seed: 12,
+            level: 3,
+            score: 40,
+            moves: 321,
+            level_moves: 17,
+            undos: 6,
+            level_hints: 2,
+            clock,
+            cut_axis: MazeAxis4::YW,
+        }
+    }
+
+    #[test]
+    fn round_trip() {
+        let save = save_game();
+        let bytes = save.to_bytes().unwrap();
+        assert_eq!(SaveGame::from_bytes(&bytes, "test.save").unwrap(), save);
+    }
+
+    #[test]
+    fn round_trip_with_pack() {
+        let mut save = save_game();
+        save.settings.pack = Some(LevelPack {
+            levels: vec![SavedMaze::new(gen_maze_with_seed((5, 5, 5, 5), 1))],
+        });
+        let bytes = save.to_bytes().unwrap();
+        assert_eq!(SaveGame::from_bytes(&bytes, "test.save").unwrap(), save);
+    }
+
+    #[test]
+    fn newer_saves_are_refused() {
+        let mut bytes = save_game().to_bytes().unwrap();
+        bytes[4] = 2;
+        match SaveGame::from_bytes(&bytes, "test.save") {
+            Err(MazeFileError::WrongVersion(2)) => (),
+            other => panic!("expected WrongVersion, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn broken_saves_are_refused() {
+        let bytes = save_game().to_bytes().unwrap();
+        match SaveGame::from_bytes(&bytes[..bytes.len() - 3], "test.save") {
+            Err(MazeFileError::Truncated) => (),
+            other => panic!("expected Truncated, got {:?}", other),
+        }
+        match SaveGame::from_bytes(b"4DMZ\x01\x00", "test.save") {
+            Err(MazeFileError::NotAMaze) => (),
+            other => panic!("expected NotAMaze, got {:?}", other),
+        }
+
+        let mut save = save_game();
+        save.player_pos = (0, 0, 0, 0);
+        match SaveGame::from_bytes(&save.to_bytes().unwrap(), "test.save") {
+            Err(MazeFileError::Malformed(_)) => (),
+            other => panic!("expected Malformed, got {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,426 @@
+//! Playing in a terminal, for when there is no window to open (over SSH for
+//! example). The slice is drawn with box-drawing characters in the same
+//! colours as the window, with the HUD underneath it. Every cell is 2
+//! characters wide so the maze isn't squashed.
+//!
+//! Keys are turned into piston `Key`s and handed to a `Keyboard`, so the
+//! controls file and the rebinding screen work the same as in the window.
+//! Ctrl-C quits
+use super::{
+    controls::{Keyboard, DEFAULT_CONTROLS_FILE},
+    game_state::{GameState, View},
+    maze_controller::colours,
+};
+use crate::{MazeCell, MazeSlice};
+use graphics::types::Color as Colour;
+use piston::input::keyboard::Key;
+use std::{
+    io::{self, Write},
+    thread,
+    time::{Duration, Instant},
+};
+use termion::{
+    async_stdin, clear, color, cursor, event::Key as TermKey, input::TermRead, raw::IntoRawMode,
+    screen::AlternateScreen, style,
+};
+
+/// How long to wait between frames
+const FRAME_TIME: Duration = Duration::from_millis(33);
+
+/// What is drawn over a traversable cell, most important first
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Mark {
+    Player,
+    Hint,
+    /// Open along the hidden axes, first the depth axis and then the other
+    /// hidden axis. Each says if the cell is open up and down the axis
+    Depth((bool, bool), (bool, bool)),
+}
+
+/// Runs the game in the terminal until Ctrl-C is pressed
+pub fn run(mut state: GameState) -> io::Result<()> {
+    let mut screen = AlternateScreen::from(io::stdout().into_raw_mode()?);
+    write!(screen, "{}", cursor::Hide)?;
+    let result = play(&mut state, &mut screen);
+
+    // The game is saved and the cursor comes back even if the terminal
+    // stopped working
+    let report = state.quit();
+    let reset = write!(screen, "{}{}", style::Reset, cursor::Show).and_then(|()| screen.flush());
+
+    // What happened is printed once the normal screen is back
+    drop(screen);
+    for line in report {
+        println!("{}", line);
+    }
+    result.and(reset)
+}
+
+/// Reads keys and draws frames on `screen` until Ctrl-C is pressed
+#[allow(clippy::cast_precision_loss)]
+fn play<W: Write>(state: &mut GameState, screen: &mut W) -> io::Result<()> {
+    let mut keyboard = Keyboard::load(DEFAULT_CONTROLS_FILE);
+    let mut keys = async_stdin().keys();
+    let mut last_frame = Instant::now();
+
+    loop {
+        for key in &mut keys {
+            match key? {
+                TermKey::Ctrl('c') => return Ok(()),
+                key => {
+                    if let Some(action) = piston_key(key).and_then(|key| keyboard.key_press(key)) {
+                        state.apply(action);
+                    }
+                }
+            }
+        }
+
+        let now = Instant::now();
+        let dt = now - last_frame;
+        last_frame = now;
+        state.update(dt.as_secs() as f64 + f64::from(dt.subsec_nanos()) / 1e9);
+
+        write!(screen, "{}{}", clear::All, cursor::Goto(1, 1))?;
+        let view = state.view(keyboard.controls());
+        let lines = match keyboard.overlay().or_else(|| view.overlay.clone()) {
+            Some(texts) => text_lines(texts),
+            None => {
+                let mut lines = slice_lines(&view);
+                lines.push(String::new());
+                lines.extend(text_lines(view.hud.clone()));
+                lines
+            }
+        };
+        // Raw mode doesn't go back to the start of the line by itself
+        write!(screen, "{}", lines.join("\r\n"))?;
+        screen.flush()?;
+
+        thread::sleep(FRAME_TIME);
+    }
+}
+
+/// The slice the player is in, one line for each row
+fn slice_lines(view: &View) -> Vec<String> {
+    let (first, _) = view.plane.hidden_axes();
+    let (rows, columns) = view.slice.0.dim();
+
+    let mut marks = vec![vec![None; columns]; rows];
+    for &((x, y), dir) in &view.depth_cues {
+        let (mut depth, mut other) = match marks[x][y] {
+            Some(Mark::Depth(depth, other)) => (depth, other),
+            _ => ((false, false), (false, false)),
+        };
+        let open = if dir.axis == first {
+            &mut depth
+        } else {
+            &mut other
+        };
+        if dir.positive {
+            open.0 = true;
+        } else {
+            open.1 = true;
+        }
+        marks[x][y] = Some(Mark::Depth(depth, other));
+    }
+    for &(x, y) in &view.hint {
+        marks[x][y] = Some(Mark::Hint);
+    }
+    let (x, y) = view.player;
+    marks[x][y] = Some(Mark::Player);
+
+    render_slice(&view.slice, &marks)
+}
+
+/// Draws `slice` with `marks[x][y]` over each cell
+pub fn render_slice(slice: &MazeSlice, marks: &[Vec<Option<Mark>>]) -> Vec<String> {
+    let (rows, columns) = slice.0.dim();
+    let is_wall = |x: usize, y: usize| {
+        slice
+            .0
+            .get((x, y))
+            .map_or(false, |cell| !cell.is_traversable())
+    };
+
+    (0..columns)
+        .map(|y| {
+            let mut line = String::new();
+            for x in 0..rows {
+                let cell = &slice.0[(x, y)];
+                line += &match cell {
+                    MazeCell::Wall | MazeCell::OuterWall => {
+                        let colour = if *cell == MazeCell::Wall {
+                            colours::BLUE
+                        } else {
+                            colours::GREEN
+                        };
+                        let right = is_wall(x + 1, y);
+                        let corner = box_char(
+                            y > 0 && is_wall(x, y - 1),
+                            is_wall(x, y + 1),
+                            x > 0 && is_wall(x - 1, y),
+                            right,
+                        );
+                        format!("{}{}{}", fg(colour), corner, if right { '─' } else { ' ' })
+                    }
+                    _ => {
+                        let background = match cell {
+                            MazeCell::Start => Some(colours::PINK),
+                            MazeCell::End => Some(colours::RED),
+                            _ => None,
+                        };
+                        let mark = marks[x][y].map_or_else(|| "  ".to_string(), mark_text);
+                        match background {
+                            Some(colour) => {
+                                format!("{}{}{}", bg(colour), mark, color::Bg(color::Reset))
+                            }
+                            None => mark,
+                        }
+                    }
+                };
+            }
+            line + &format!("{}", color::Fg(color::Reset))
+        })
+        .collect()
+}
+
+/// The 2 characters drawn for a mark
+fn mark_text(mark: Mark) -> String {
+    let arrow = |open| match open {
+        (true, true) => '↕',
+        (true, false) => '↑',
+        (false, true) => '↓',
+        (false, false) => ' ',
+    };
+    match mark {
+        Mark::Player => format!("{}@@", fg(colours::YELLOW)),
+        Mark::Hint => format!("{}**", fg(colours::ORANGE)),
+        Mark::Depth(depth, other) => format!(
+            "{}{}{}{}",
+            fg(colours::PURPLE),
+            arrow(depth),
+            fg(colours::CYAN),
+            arrow(other)
+        ),
+    }
+}
+
+/// The box-drawing character for a wall joined to the walls it touches
+#[allow(clippy::fn_params_excessive_bools)]
+fn box_char(up: bool, down: bool, left: bool, right: bool) -> char {
+    match (up, down, left, right) {
+        (false, false, false, false) => '■',
+        (_, _, false, false) => '│',
+        (false, false, _, _) => '─',
+        (false, true, false, true) => '┌',
+        (false, true, true, false) => '┐',
+        (true, false, false, true) => '└',
+        (true, false, true, false) => '┘',
+        (true, true, false, true) => '├',
+        (true, true, true, false) => '┤',
+        (false, true, true, true) => '┬',
+        (true, false, true, true) => '┴',
+        (true, true, true, true) => '┼',
+    }
+}
+
+/// Lays the lines of text out in the order they are on the screen. Texts at
+/// the same height go on one line
+#[allow(clippy::float_cmp)]
+fn text_lines(mut texts: Vec<(String, [f64; 2])>) -> Vec<String> {
+    texts.sort_by(|(_, first), (_, second)| {
+        (first[1], first[0])
+            .partial_cmp(&(second[1], second[0]))
+            .unwrap_or(std::cmp::Ordering::Equal)
+    });
+
+    let mut lines: Vec<(f64, String)> = Vec::new();
+    for (text, [_, y]) in texts {
+        match lines.last_mut() {
+            Some((last_y, line)) if *last_y == y => {
+                *line += "   ";
+                *line += &text;
+            }
+            _ => lines.push((y, text)),
+        }
+    }
+    lines.into_iter().map(|(_, line)| line).collect()
+}
+
+/// The piston key for a terminal key, if the game has one
+fn piston_key(key: TermKey) -> Option<Key> {
+    Some(match key {
+        TermKey::Left => Key::Left,
+        TermKey::Right => Key::Right,
+        TermKey::Up => Key::Up,
+        TermKey::Down => Key::Down,
+        TermKey::PageUp => Key::PageUp,
+        TermKey::PageDown => Key::PageDown,
+        TermKey::Home => Key::Home,
+        TermKey::End => Key::End,
+        TermKey::Insert => Key::Insert,
+        TermKey::Delete => Key::Delete,
+        TermKey::Backspace => Key::Backspace,
+        TermKey::Esc => Key::Escape,
+        TermKey::F(number) => match number {
+            1 => Key::F1,
+            2 => Key::F2,
+            3 => Key::F3,
+            4 => Key::F4,
+            5 => Key::F5,
+            6 => Key::F6,
+            7 => Key::F7,
+            8 => Key::F8,
+            9 => Key::F9,
+            10 => Key::F10,
+            11 => Key::F11,
+            12 => Key::F12,
+            _ => return None,
+        },
+        TermKey::Char(ch) => char_key(ch)?,
+        _ => return None,
+    })
+}
+
+/// The key that types `ch`. Capitals are the same key as small letters
+fn char_key(ch: char) -> Option<Key> {
+    const LETTERS: [Key; 26] = [
+        Key::A,
+        Key::B,
+        Key::C,
+        Key::D,
+        Key::E,
+        Key::F,
+        Key::G,
+        Key::H,
+        Key::I,
+        Key::J,
+        Key::K,
+        Key::L,
+        Key::M,
+        Key::N,
+        Key::O,
+        Key::P,
+        Key::Q,
+        Key::R,
+        Key::S,
+        Key::T,
+        Key::U,
+        Key::V,
+        Key::W,
+        Key::X,
+        Key::Y,
+        Key::Z,
+    ];
+    const DIGITS: [Key; 10] = [
+        Key::D0,
+        Key::D1,
+        Key::D2,
+        Key::D3,
+        Key::D4,
+        Key::D5,
+        Key::D6,
+        Key::D7,
+        Key::D8,
+        Key::D9,
+    ];
+
+    match ch {
+        'a'..='z' => Some(LETTERS[ch as usize - 'a' as usize]),
+        'A'..='Z' => Some(LETTERS[ch as usize - 'A' as usize]),
+        '0'..='9' => Some(DIGITS[ch as usize - '0' as usize]),
+        '\n' | '\r' => Some(Key::Return),
+        '\t' => Some(Key::Tab),
+        ' ' => Some(Key::Space),
+        '-' => Some(Key::Minus),
+        '=' => Some(Key::Equals),
+        ',' => Some(Key::Comma),
+        '.' => Some(Key::Period),
+        '/' => Some(Key::Slash),
+        '`' => Some(Key::Backquote),
+        _ => None,
+    }
+}
+
+#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+fn rgb(colour: Colour) -> color::Rgb {
+    let channel = |value: f32| (value * 255.0).round() as u8;
+    color::Rgb(channel(colour[0]), channel(colour[1]), channel(colour[2]))
+}
+
+fn fg(colour: Colour) -> color::Fg<color::Rgb> {
+    color::Fg(rgb(colour))
+}
+
+fn bg(colour: Colour) -> color::Bg<color::Rgb> {
+    color::Bg(rgb(colour))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Maze, MazeAxis4};
+
+    /// Takes the colours out so the characters can be compared
+    fn plain(line: &str) -> String {
+        let mut plain = String::new();
+        let mut in_escape = false;
+        for ch in line.chars() {
+            match ch {
+                '\x1b' => in_escape = true,
+                'm' if in_escape => in_escape = false,
+                _ if !in_escape => plain.push(ch),
+                _ => {}
+            }
+        }
+        plain
+    }
+
+    #[test]
+    fn walls_are_joined() {
+        // A 5x3 room with one wall sticking down into it
+        let mut maze = Maze::new_empty((5, 3, 3, 3));
+        for x in 1..4 {
+            maze.0[(x, 1, 1, 1)] = MazeCell::Cell;
+        }
+        maze.0[(2, 1, 1, 1)] = MazeCell::Wall;
+        for x in 0..5 {
+            maze.0[(x, 0, 1, 1)] = MazeCell::OuterWall;
+            maze.0[(x, 2, 1, 1)] = MazeCell::OuterWall;
+        }
+        for y in 0..3 {
+            maze.0[(0, y, 1, 1)] = MazeCell::OuterWall;
+            maze.0[(4, y, 1, 1)] = MazeCell::OuterWall;
+        }
+        let slice = maze.view_2_axis(MazeAxis4::XY, (1, 1, 1, 1));
+
+        let mut marks = vec![vec![None; 3]; 5];
+        marks[1][1] = Some(Mark::Player);
+        marks[3][1] = Some(Mark::Depth((true, false), (false, false)));
+        let lines = render_slice(&slice, &marks)
+            .iter()
+            .map(|line| plain(line))
+            .collect::<Vec<_>>();
+
+        assert_eq!(lines, vec!["┌───┬───┐ ", "│ @@│ ↑ │ ", "└───┴───┘ "]);
+    }
+
+    #[test]
+    fn keys() {
+        assert_eq!(piston_key(TermKey::Char('w')), Some(Key::W));
+        assert_eq!(piston_key(TermKey::Char('W')), Some(Key::W));
+        assert_eq!(piston_key(TermKey::Char('\n')), Some(Key::Return));
+        assert_eq!(piston_key(TermKey::F(5)), Some(Key::F5));
+        assert_eq!(piston_key(TermKey::PageDown), Some(Key::PageDown));
+        assert_eq!(piston_key(TermKey::Char('£')), None);
+    }
+
+    #[test]
+    fn text_layout() {
+        let texts = vec![
+            ("Time".to_string(), [260.0, 150.0]),
+            ("Seed".to_string(), [10.0, 875.0]),
+            ("Moves".to_string(), [10.0, 150.0]),
+        ];
+        assert_eq!(text_lines(texts), vec!["Moves   Time", "Seed"]);
+    }
+}
@@ -0,0 +1,186 @@
+//! Keeps time for the game. The clock is only moved on by `tick`, which the
+//! game calls with the `dt` of each piston update event, so the clock never
+//! reads the real time and can be tested by ticking it by hand
+use std::cmp::Ordering;
+
+/// The rules for time-attack mode: the game starts with `budget` seconds and
+/// every maze solved adds `bonus` seconds
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TimeAttack {
+    pub budget: f64,
+    pub bonus: f64,
+}
+
+/// The level and session timers, and the time left in time-attack mode
+#[derive(Clone, Debug, PartialEq)]
+pub struct GameClock {
+    level: f64,
+    session: f64,
+    remaining: Option<f64>,
+    bonus: f64,
+    focused: bool,
+    paused: bool,
+}
+
+impl GameClock {
+    /// A clock at 0. `time_attack` gives the game a time limit
+    pub fn new(time_attack: Option<TimeAttack>) -> Self {
+        Self {
+            level: 0.0,
+            session: 0.0,
+            remaining: time_attack.map(|rules| rules.budget),
+            bonus: time_attack.map_or(0.0, |rules| rules.bonus),
+            focused: true,
+            paused: false,
+        }
+    }
+
+    /// A clock that carries on from a saved game
+    pub fn resume(
+        time_attack: Option<TimeAttack>,
+        level: f64,
+        session: f64,
+        remaining: Option<f64>,
+    ) -> Self {
+        Self {
+            level,
+            session,
+            remaining: remaining.or_else(|| time_attack.map(|rules| rules.budget)),
+            ..Self::new(time_attack)
+        }
+    }
+
+    /// Moves the clock on by `dt` seconds, unless it is paused or the time
+    /// has run out
+    pub fn tick(&mut self, dt: f64) {
+        if self.is_paused() || self.is_out_of_time() {
+            return;
+        }
+
+        self.level += dt;
+        self.session += dt;
+        if let Some(remaining) = &mut self.remaining {
+            *remaining = (*remaining - dt).max(0.0);
+        }
+    }
+
+    /// The clock stops while the window doesn't have focus
+    pub fn set_focus(&mut self, focused: bool) {
+        self.focused = focused;
+    }
+
+    /// Stops or starts the clock when the player pauses the game
+    pub fn toggle_pause(&mut self) {
+        self.paused = !self.paused;
+    }
+
+    /// Whether the clock is stopped, by the player or by losing focus
+    pub fn is_paused(&self) -> bool {
+        self.paused || !self.focused
+    }
+
+    /// Starts the next level, adding the bonus in time-attack mode
+    pub fn level_finished(&mut self) {
+        self.level = 0.0;
+        if let Some(remaining) = &mut self.remaining {
+            *remaining += self.bonus;
+        }
+    }
+
+    /// Seconds spent on this level
+    pub fn level_time(&self) -> f64 {
+        self.level
+    }
+
+    /// Seconds spent on the whole game
+    pub fn session_time(&self) -> f64 {
+        self.session
+    }
+
+    /// Seconds left in time-attack mode
+    pub fn remaining(&self) -> Option<f64> {
+        self.remaining
+    }
+
+    /// Whether time-attack mode has run out of time
+    pub fn is_out_of_time(&self) -> bool {
+        self.remaining.map_or(false, |remaining| {
+            remaining.partial_cmp(&0.0) != Some(Ordering::Greater)
+        })
+    }
+}
+
+/// Formats `seconds` as minutes and seconds, like `2:05`
+#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+pub fn format_time(seconds: f64) -> String {
+    let seconds = seconds.max(0.0) as u64;
+    format!("{}:{:02}", seconds / 60, seconds % 60)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RULES: TimeAttack = TimeAttack {
+        budget: 10.0,
+        bonus: 5.0,
+    };
+
+    #[test]
+    fn level_and_session() {
+        let mut clock = GameClock::new(None);
+        clock.tick(1.5);
+        clock.tick(2.0);
+        clock.level_finished();
+        clock.tick(0.5);
+
+        assert_eq!(clock.level_time(), 0.5);
+        assert_eq!(clock.session_time(), 4.0);
+        assert_eq!(clock.remaining(), None);
+        assert!(!clock.is_out_of_time());
+    }
+
+    #[test]
+    fn pauses_without_focus() {
+        let mut clock = GameClock::new(Some(RULES));
+        clock.tick(1.0);
+        clock.set_focus(false);
+        clock.tick(100.0);
+        assert!(clock.is_paused());
+        clock.set_focus(true);
+        clock.tick(1.0);
+
+        assert_eq!(clock.session_time(), 2.0);
+        assert_eq!(clock.remaining(), Some(8.0));
+
+        clock.toggle_pause();
+        clock.tick(100.0);
+        assert!(clock.is_paused());
+        clock.toggle_pause();
+        assert!(!clock.is_paused());
+        assert_eq!(clock.session_time(), 2.0);
+    }
+
+    #[test]
+    fn time_attack() {
+        let mut clock = GameClock::new(Some(RULES));
+        clock.tick(6.0);
+        clock.level_finished();
+        assert_eq!(clock.remaining(), Some(9.0));
+
+        clock.tick(9.5);
+        assert!(clock.is_out_of_time());
+        assert_eq!(clock.remaining(), Some(0.0));
+
+        // The clock stops once the time has run out
+        clock.tick(3.0);
+        assert_eq!(clock.session_time(), 15.5);
+    }
+
+    #[test]
+    fn formatting() {
+        assert_eq!(format_time(0.0), "0:00");
+        assert_eq!(format_time(125.9), "2:05");
+        assert_eq!(format_time(-3.0), "0:00");
+    }
+}
@@ -0,0 +1,70 @@
+//! The window the piston and ECS frontends are played in. Both open the same
+//! SDL2 window with OpenGL and the same font, and only differ in how they turn
+//! the events into moves and how they draw the game
+use super::render::DEFAULT_FONT_FILE;
+use graphics::{types::Color as Colour, Context};
+use opengl_graphics::{Filter, GlGraphics, GlyphCache, OpenGL, TextureSettings};
+use piston::{
+    event_loop::{EventSettings, Events},
+    input::{CloseEvent, Event, RenderEvent, UpdateEvent},
+    window::WindowSettings,
+};
+use sdl2_window::Sdl2Window;
+
+/// A frontend that is played in the window
+pub trait WindowFrontend {
+    /// Handles the input in `e`
+    fn event(&mut self, e: &Event);
+
+    /// Moves the game on by `dt` seconds
+    fn update(&mut self, dt: f64);
+
+    /// Records and saves the game as the window closes. Returns what happened
+    /// for the player to read in the terminal
+    fn quit(&mut self) -> Vec<String>;
+
+    fn draw(&self, glyphs: &mut GlyphCache, c: &Context, g: &mut GlGraphics);
+}
+
+/// Opens a window with `settings` and plays `frontend` in it until the window
+/// is closed. Every frame is cleared to `background` before it is drawn
+pub fn run<F: WindowFrontend>(settings: &WindowSettings, background: Colour, frontend: &mut F) {
+    let mut window: Sdl2Window = settings.build().expect("Window Creation failed");
+    // Gamepads are only reported once SDL has opened them
+    if let Err(error) = window.init_joysticks() {
+        eprintln!("Could not open the gamepads: {}", error);
+    }
+    let mut events = Events::new(EventSettings::new());
+
+    // Here we set up OpenGL
+    let opengl = OpenGL::V3_2;
+    let mut gl = GlGraphics::new(opengl);
+
+    let texture_settings = TextureSettings::new().filter(Filter::Nearest);
+    let glyphs =
+        &mut GlyphCache::new(DEFAULT_FONT_FILE, (), texture_settings).expect("Could not load font");
+
+    while let Some(e) = events.next(&mut window) {
+        frontend.event(&e);
+
+        if let Some(args) = e.update_args() {
+            frontend.update(args.dt);
+        }
+
+        // The game is saved when the window is closed so it can be continued
+        if e.close_args().is_some() {
+            for line in frontend.quit() {
+                println!("{}", line);
+            }
+        }
+
+        if let Some(args) = e.render_args() {
+            gl.draw(args.viewport(), |c, g| {
+                use graphics::clear;
+
+                clear(background, g);
+                frontend.draw(glyphs, &c, g)
+            });
+        }
+    }
+}
@@ -11,13 +11,290 @@ extern crate ndarray;
 pub mod game;
 pub mod maze_lib;
 
+use crate::game::{
+    high_scores::DEFAULT_SCORES_FILE,
+    maze_controller::GameSettings,
+    render::save_first_maze,
+    save::{SaveGame, DEFAULT_SAVE_FILE},
+    timer::TimeAttack,
+};
+use std::path::PathBuf;
+use std::str::FromStr;
+
 pub use crate::maze_lib::{
-    maze_base::{Maze, MazeAxis3, MazeCell, MazeSlice},
-    maze_gen::gen_maze,
+    maze_algorithms::{Algorithm, PickStrategy},
+    maze_analysis::{analyse, MazeReport},
+    maze_base::{Maze, MazeAxis3, MazeAxis4, MazeCell, MazeMode, MazePos, MazeSlice},
+    maze_file::{Format, LevelPack, MazeFileError, SavedMaze, FORMAT_VERSION},
+    maze_gen::{
+        gen_maze, gen_maze_with, gen_maze_with_options, gen_maze_with_seed, seeded_rng,
+        MazeGenerator, MazeOptions,
+    },
+    maze_move::{try_move, Direction, Move, MoveError},
+    maze_placement::{ExitPlacement, StartPlacement},
+    maze_solve::{
+        distances, par, shortest_path, slice_path, solve, solve_with_rotations, AStar,
+        BreadthFirst, MazeSolver, WallFollower,
+    },
 };
 
-pub(crate) use crate::maze_lib::MAZE_SIZE;
+pub(crate) use crate::maze_lib::DEFAULT_MAZE_SIZE;
 
 fn main() {
-    game::run();
+    let args: Vec<String> = std::env::args().collect();
+
+    // The game opens a window unless `--frontend terminal` is given or it was
+    // built without the `window` feature, and `--frontend ecs` draws it with
+    // the entity component system instead
+    let frontend = parse_flag(&args, "--frontend", "terminal").unwrap_or_default();
+
+    // The game is saved with F5 and on quit, and `--continue` carries on
+    // from the save. `--save-file` picks a different file
+    let save_path = match args.iter().position(|arg| arg == "--save-file") {
+        Some(index) => match args.get(index + 1) {
+            Some(path) => PathBuf::from(path),
+            None => {
+                eprintln!("--save-file needs the path of a save");
+                std::process::exit(1);
+            }
+        },
+        None => PathBuf::from(DEFAULT_SAVE_FILE),
+    };
+    if args.iter().any(|arg| arg == "--continue") {
+        match SaveGame::load(&save_path) {
+            Ok(save) => game::continue_game(save, frontend),
+            Err(error) => {
+                eprintln!("Could not continue from {}: {}", save_path.display(), error);
+                std::process::exit(1);
+            }
+        }
+        return;
+    }
+
+    // The size can be chosen with `--size 21x21x5` (3D) or `--size 9x9x9x5`
+    let size = match args.iter().position(|arg| arg == "--size") {
+        Some(index) => match args.get(index + 1).map(|size| parse_size(size)) {
+            Some(Ok(size)) => Some(size),
+            Some(Err(error)) => {
+                eprintln!("{}", error);
+                std::process::exit(1);
+            }
+            None => {
+                eprintln!("--size needs a size such as 21x21x5");
+                std::process::exit(1);
+            }
+        },
+        None => None,
+    };
+
+    // The 3D maze is still available with `--3d` or by giving a 3D size
+    let mode = if args.iter().any(|arg| arg == "--3d") || size.map_or(false, |(_, is_3d)| is_3d) {
+        MazeMode::ThreeD
+    } else {
+        MazeMode::FourD
+    };
+    let shape = size.map_or_else(|| mode.default_shape(), |(shape, _)| mode.shape(shape));
+    // `--3d` flattens W, which can leave too few rooms
+    if let Err(error) = check_rooms(shape) {
+        eprintln!("{}", error);
+        std::process::exit(1);
+    }
+
+    // A seed shown in the game can be played again with `--seed`
+    let seed = match args.iter().position(|arg| arg == "--seed") {
+        Some(index) => match args.get(index + 1).map(|seed| seed.parse::<u64>()) {
+            Some(Ok(seed)) => Some(seed),
+            _ => {
+                eprintln!("--seed needs a whole number such as 1234");
+                std::process::exit(1);
+            }
+        },
+        None => None,
+    };
+
+    // The algorithm that carves each maze can be chosen with `--algorithm prim`
+    let algorithm = parse_flag(&args, "--algorithm", "prim").unwrap_or_default();
+
+    // The start and end can be placed with `--start random --exit diameter`
+    let mut options = GameSettings::default().options;
+    if let Some(start) = parse_flag(&args, "--start", "1,1,1,1") {
+        options.start = start;
+    }
+    if let Some(exit) = parse_flag(&args, "--exit", "farthest") {
+        options.exit = exit;
+    }
+
+    // Loops can be added with `--braid 50`, which removes half the dead ends
+    if let Some(index) = args.iter().position(|arg| arg == "--braid") {
+        match args.get(index + 1).map(|braid| braid.parse::<u8>()) {
+            Some(Ok(braid)) if braid <= 100 => options.braid = braid,
+            _ => {
+                eprintln!("--braid needs a percentage of dead ends to remove such as 50");
+                std::process::exit(1);
+            }
+        }
+    }
+    if let StartPlacement::At(pos) = options.start {
+        if pos.0 >= shape.0 - 1
+            || pos.1 >= shape.1 - 1
+            || pos.2 >= shape.2 - 1
+            || pos.3 >= shape.3 - 1
+        {
+            eprintln!("The start {} is outside the maze", options.start);
+            std::process::exit(1);
+        }
+    }
+
+    // High scores are recorded under `--name`, or the name of the user
+    let player_name = match args.iter().position(|arg| arg == "--name") {
+        Some(index) => match args.get(index + 1) {
+            Some(name) => name.clone(),
+            None => {
+                eprintln!("--name needs the name to record high scores under");
+                std::process::exit(1);
+            }
+        },
+        None => std::env::var("USER")
+            .or_else(|_| std::env::var("USERNAME"))
+            .unwrap_or_else(|_| GameSettings::default().player_name),
+    };
+
+    // Hand-picked levels can be played first with `--pack levels.txt`
+    let pack = args.iter().position(|arg| arg == "--pack").map(|index| {
+        let path = args.get(index + 1).unwrap_or_else(|| {
+            eprintln!("--pack needs the path of a level pack");
+            std::process::exit(1);
+        });
+        LevelPack::load(path).unwrap_or_else(|error| {
+            eprintln!("{}: {}", path, error);
+            std::process::exit(1);
+        })
+    });
+
+    // `--time-attack 120` gives 2 minutes to solve as many mazes as possible,
+    // with `--bonus` seconds (15 unless given) added for each one
+    let time_attack = parse_seconds(&args, "--time-attack").map(|budget| TimeAttack {
+        budget,
+        bonus: parse_seconds(&args, "--bonus").unwrap_or(15.0),
+    });
+
+    let settings = GameSettings {
+        mode,
+        shape,
+        seed,
+        algorithm,
+        options,
+        pack,
+        save_path,
+        scores_path: PathBuf::from(DEFAULT_SCORES_FILE),
+        player_name,
+        time_attack,
+    };
+
+    // `--png maze.png` draws the first maze into a picture instead of playing
+    // it, which works without a screen
+    if let Some(index) = args.iter().position(|arg| arg == "--png") {
+        let path = args.get(index + 1).unwrap_or_else(|| {
+            eprintln!("--png needs the path of the picture to write");
+            std::process::exit(1);
+        });
+        if let Err(error) = save_first_maze(settings, path) {
+            eprintln!("Could not write {}: {}", path, error);
+            std::process::exit(1);
+        }
+        return;
+    }
+
+    game::run(settings, frontend);
+}
+
+/// Parses the number of seconds after `flag`, if it was given
+fn parse_seconds(args: &[String], flag: &str) -> Option<f64> {
+    let index = args.iter().position(|arg| arg == flag)?;
+    match args.get(index + 1).map(|seconds| seconds.parse::<f64>()) {
+        Some(Ok(seconds)) if seconds.is_finite() && seconds >= 0.0 => Some(seconds),
+        _ => {
+            eprintln!("{} needs a number of seconds such as 60", flag);
+            std::process::exit(1);
+        }
+    }
+}
+
+/// Parses the value after `flag`, if it was given. The game exits with an
+/// error if the value is missing or can't be parsed
+fn parse_flag<T: FromStr<Err = String>>(args: &[String], flag: &str, example: &str) -> Option<T> {
+    let index = args.iter().position(|arg| arg == flag)?;
+    match args.get(index + 1).map(|value| value.parse::<T>()) {
+        Some(Ok(value)) => Some(value),
+        Some(Err(error)) => {
+            eprintln!("{}", error);
+            std::process::exit(1);
+        }
+        None => {
+            eprintln!("{} needs a value such as {}", flag, example);
+            std::process::exit(1);
+        }
+    }
+}
+
+/// Parses a size like `21x21x5` or `9x9x9x5`. The bool is true if only 3 axes
+/// were given
+fn parse_size(size: &str) -> Result<(MazePos, bool), String> {
+    let axes = size
+        .split('x')
+        .map(|axis| {
+            axis.parse::<usize>()
+                .map_err(|_| format!("{:?} is not a valid maze size", axis))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    if axes.iter().any(|&axis| axis < 3) {
+        return Err("Every axis of the maze needs to be at least 3 cells long".to_string());
+    }
+
+    let size = match axes.as_slice() {
+        [x, y, z] => ((*x, *y, *z, 3), true),
+        [x, y, z, w] => ((*x, *y, *z, *w), false),
+        _ => return Err(format!("{:?} should have 3 or 4 axes", size)),
+    };
+    check_rooms(size.0)?;
+    Ok(size)
+}
+
+/// A maze needs at least 2 rooms so the start and the end aren't the same
+/// room. Rooms are at odd co-ordinates inside the outer wall, so an axis `n`
+/// cells long has `(n - 1) / 2` of them
+fn check_rooms(shape: MazePos) -> Result<(), String> {
+    let (x, y, z, w) = shape;
+    let rooms: usize = [x, y, z, w].iter().map(|axis| (axis - 1) / 2).product();
+    if rooms < 2 {
+        return Err(
+            "The maze needs at least 2 rooms, so one axis has to be at least 5 cells long"
+                .to_string(),
+        );
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sizes_need_2_rooms() {
+        assert_eq!(parse_size("5x3x3"), Ok(((5, 3, 3, 3), true)));
+        assert_eq!(parse_size("3x3x3x5"), Ok(((3, 3, 3, 5), false)));
+        assert!(parse_size("3x3x3").is_err());
+        assert!(parse_size("4x4x4x4").is_err());
+        assert!(check_rooms(MazeMode::ThreeD.shape((3, 3, 3, 9))).is_err());
+
+        // The smallest maze still has a separate start and end
+        let settings = GameSettings {
+            mode: MazeMode::ThreeD,
+            shape: (5, 3, 3, 3),
+            ..GameSettings::default()
+        };
+        let maze = settings.gen_maze(1);
+        assert_ne!(maze.start_pos(), maze.end_pos());
+    }
 }
@@ -0,0 +1,551 @@
+//! The other algorithms that can carve a maze. Every one of them makes a
+//! perfect maze (there is exactly one route between any 2 rooms) but each has
+//! its own texture, so a level can pick the one that suits it
+use crate::maze_lib::maze_gen::{
+    carve_passage, neighbours, pick, rooms, shuffle, MazeGenerator, RecursiveBacktracker,
+};
+use crate::{Maze, MazeCell, MazePos};
+use rand::{Rng, RngCore};
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt,
+    str::FromStr,
+};
+
+/// Every algorithm that can generate a maze, so one can be chosen at runtime
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Algorithm {
+    RecursiveBacktracker,
+    Prim,
+    Kruskal,
+    Wilson,
+    Eller,
+    AldousBroder,
+    HuntAndKill,
+    GrowingTree(PickStrategy),
+}
+
+/// Randomized Prim's algorithm. It grows the maze from a random wall on its
+/// edge, which gives lots of short dead ends
+pub struct Prim;
+
+/// Kruskal's algorithm. It knocks through walls in a random order whenever
+/// they join 2 separate parts of the maze
+pub struct Kruskal;
+
+/// Wilson's algorithm. Loop-erased random walks give a maze picked uniformly
+/// from every possible maze
+pub struct Wilson;
+
+/// Eller's algorithm. The maze is built one X layer at a time, joining rooms
+/// within the layer and then dropping at least one passage from every group
+/// into the next layer
+pub struct Eller;
+
+/// The Aldous-Broder algorithm. A random walk that carves whenever it reaches
+/// a new room. Like `Wilson` it picks uniformly from every possible maze
+pub struct AldousBroder;
+
+/// Hunt-and-kill. It walks randomly until it gets stuck and then hunts for the
+/// first unvisited room next to the maze
+pub struct HuntAndKill;
+
+/// The growing tree algorithm. How it picks the next room to grow from decides
+/// what the maze looks like
+pub struct GrowingTree(pub PickStrategy);
+
+/// How `GrowingTree` picks the room to grow from
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PickStrategy {
+    /// The room added last (the same as `RecursiveBacktracker`)
+    Newest,
+    /// The room added first, which gives long straight corridors
+    Oldest,
+    /// Any room (similar to `Prim`)
+    Random,
+    /// The newest room this percentage of the time, otherwise a random one
+    Mixed(u8),
+}
+
+/// A union-find over room indices
+struct DisjointSet(Vec<usize>);
+
+impl DisjointSet {
+    fn new(len: usize) -> Self {
+        DisjointSet((0..len).collect())
+    }
+
+    fn find(&mut self, mut index: usize) -> usize {
+        while self.0[index] != index {
+            // Path halving keeps the trees shallow
+            self.0[index] = self.0[self.0[index]];
+            index = self.0[index];
+        }
+
+        index
+    }
+
+    /// Joins the sets holding `a` and `b`. Returns false if they were already
+    /// joined
+    fn union(&mut self, a: usize, b: usize) -> bool {
+        let (a, b) = (self.find(a), self.find(b));
+        self.0[a] = b;
+
+        a != b
+    }
+}
+
+/// Every pair of neighbouring rooms once, with the wall between them
+fn edges(maze: &Maze, rooms: &[MazePos]) -> Vec<(MazePos, MazePos, MazePos)> {
+    rooms
+        .iter()
+        .flat_map(|&room| {
+            neighbours(maze, room)
+                .into_iter()
+                .filter(move |&(next, _)| next > room)
+                .map(move |(next, wall)| (room, wall, next))
+        })
+        .collect()
+}
+
+fn is_unvisited(maze: &Maze, room: MazePos) -> bool {
+    maze.0[room] == MazeCell::Unvisited
+}
+
+impl MazeGenerator for Prim {
+    fn carve(&self, maze: &mut Maze, start: MazePos, rng: &mut dyn RngCore) -> MazePos {
+        let mut last = start;
+        maze.0[start] = MazeCell::Cell;
+        let mut frontier = neighbours(maze, start);
+
+        while !frontier.is_empty() {
+            let (room, wall) = frontier.swap_remove(pick(rng, frontier.len()));
+
+            if is_unvisited(maze, room) {
+                carve_passage(maze, wall, room);
+                frontier.extend(
+                    neighbours(maze, room)
+                        .into_iter()
+                        .filter(|&(next, _)| is_unvisited(maze, next)),
+                );
+                last = room;
+            }
+        }
+
+        last
+    }
+}
+
+impl MazeGenerator for Kruskal {
+    fn carve(&self, maze: &mut Maze, start: MazePos, rng: &mut dyn RngCore) -> MazePos {
+        let rooms = rooms(maze);
+        let index: HashMap<MazePos, usize> =
+            rooms.iter().enumerate().map(|(i, &r)| (r, i)).collect();
+        let mut sets = DisjointSet::new(rooms.len());
+        let mut edges = edges(maze, &rooms);
+        shuffle(rng, &mut edges);
+
+        let mut last = start;
+        maze.0[start] = MazeCell::Cell;
+
+        for (room, wall, next) in edges {
+            if sets.union(index[&room], index[&next]) {
+                carve_passage(maze, wall, next);
+                maze.0[room] = MazeCell::Cell;
+                last = next;
+            }
+        }
+
+        last
+    }
+}
+
+impl MazeGenerator for Wilson {
+    fn carve(&self, maze: &mut Maze, start: MazePos, rng: &mut dyn RngCore) -> MazePos {
+        let mut rooms = rooms(maze);
+        shuffle(rng, &mut rooms);
+
+        let mut last = start;
+        maze.0[start] = MazeCell::Cell;
+
+        for &first in &rooms {
+            if !is_unvisited(maze, first) {
+                continue;
+            }
+
+            // Walk until the maze is reached, remembering only the last way
+            // out of each room so any loops are erased
+            let mut exits: HashMap<MazePos, (MazePos, MazePos)> = HashMap::new();
+            let mut room = first;
+            while is_unvisited(maze, room) {
+                let options = neighbours(maze, room);
+                let exit = options[pick(rng, options.len())];
+                exits.insert(room, exit);
+                room = exit.0;
+            }
+
+            // Carve the loop-erased path
+            room = first;
+            maze.0[room] = MazeCell::Cell;
+            while let Some(&(next, wall)) = exits.get(&room) {
+                if !is_unvisited(maze, next) {
+                    maze.0[wall] = MazeCell::Cell;
+                    break;
+                }
+                carve_passage(maze, wall, next);
+                room = next;
+            }
+            last = room;
+        }
+
+        last
+    }
+}
+
+impl MazeGenerator for Eller {
+    fn carve(&self, maze: &mut Maze, start: MazePos, rng: &mut dyn RngCore) -> MazePos {
+        let rooms = rooms(maze);
+        let index: HashMap<MazePos, usize> =
+            rooms.iter().enumerate().map(|(i, &r)| (r, i)).collect();
+        let mut sets = DisjointSet::new(rooms.len());
+
+        // Group the rooms into layers along X
+        let mut layers: BTreeMap<usize, Vec<MazePos>> = BTreeMap::new();
+        for &room in &rooms {
+            layers.entry(room.0).or_insert_with(Vec::new).push(room);
+        }
+        let last_layer = *layers.keys().next_back().unwrap();
+
+        let mut last = start;
+        for cell in maze.0.iter_mut() {
+            if *cell == MazeCell::Unvisited {
+                *cell = MazeCell::Cell;
+            }
+        }
+
+        for (&layer_x, layer) in &layers {
+            let is_last = layer_x == last_layer;
+
+            // Randomly join rooms within the layer that are not yet joined. The
+            // last layer has to join everything
+            let mut in_layer: Vec<_> = edges(maze, layer)
+                .into_iter()
+                .filter(|&(_, _, next)| next.0 == layer_x)
+                .collect();
+            shuffle(rng, &mut in_layer);
+
+            for (room, wall, next) in in_layer {
+                if (is_last || rng.gen_range(0_u32, 2) == 0)
+                    && sets.union(index[&room], index[&next])
+                {
+                    maze.0[wall] = MazeCell::Cell;
+                    last = next;
+                }
+            }
+
+            if is_last {
+                break;
+            }
+
+            // Every group needs at least one passage down into the next layer
+            let mut groups: BTreeMap<usize, Vec<MazePos>> = BTreeMap::new();
+            for &room in layer {
+                groups
+                    .entry(sets.find(index[&room]))
+                    .or_insert_with(Vec::new)
+                    .push(room);
+            }
+
+            for group in groups.values() {
+                let forced = pick(rng, group.len());
+                for (pos, &room) in group.iter().enumerate() {
+                    if pos == forced || rng.gen_range(0_u32, 2) == 0 {
+                        let (next, wall) = (
+                            (room.0 + 2, room.1, room.2, room.3),
+                            (room.0 + 1, room.1, room.2, room.3),
+                        );
+                        sets.union(index[&room], index[&next]);
+                        maze.0[wall] = MazeCell::Cell;
+                        last = next;
+                    }
+                }
+            }
+        }
+
+        last
+    }
+}
+
+impl MazeGenerator for AldousBroder {
+    fn carve(&self, maze: &mut Maze, start: MazePos, rng: &mut dyn RngCore) -> MazePos {
+        let mut remaining = rooms(maze).len() - 1;
+        let mut room = start;
+        let mut last = start;
+        maze.0[start] = MazeCell::Cell;
+
+        while remaining > 0 {
+            let options = neighbours(maze, room);
+            let (next, wall) = options[pick(rng, options.len())];
+
+            if is_unvisited(maze, next) {
+                carve_passage(maze, wall, next);
+                remaining -= 1;
+                last = next;
+            }
+            room = next;
+        }
+
+        last
+    }
+}
+
+impl MazeGenerator for HuntAndKill {
+    fn carve(&self, maze: &mut Maze, start: MazePos, rng: &mut dyn RngCore) -> MazePos {
+        let rooms = rooms(maze);
+        let mut room = start;
+        let mut last = start;
+        maze.0[start] = MazeCell::Cell;
+
+        loop {
+            // Kill: walk to random unvisited rooms until there are none
+            let options: Vec<_> = neighbours(maze, room)
+                .into_iter()
+                .filter(|&(next, _)| is_unvisited(maze, next))
+                .collect();
+
+            if !options.is_empty() {
+                let (next, wall) = options[pick(rng, options.len())];
+                carve_passage(maze, wall, next);
+                room = next;
+                last = next;
+                continue;
+            }
+
+            // Hunt: find the first unvisited room next to the maze and join it
+            let hunted = rooms.iter().find_map(|&candidate| {
+                if !is_unvisited(maze, candidate) {
+                    return None;
+                }
+
+                let visited: Vec<_> = neighbours(maze, candidate)
+                    .into_iter()
+                    .filter(|&(next, _)| !is_unvisited(maze, next))
+                    .collect();
+
+                if visited.is_empty() {
+                    None
+                } else {
+                    Some((candidate, visited))
+                }
+            });
+
+            match hunted {
+                Some((candidate, visited)) => {
+                    let (_, wall) = visited[pick(rng, visited.len())];
+                    carve_passage(maze, wall, candidate);
+                    room = candidate;
+                    last = candidate;
+                }
+                None => break,
+            }
+        }
+
+        last
+    }
+}
+
+impl MazeGenerator for GrowingTree {
+    fn carve(&self, maze: &mut Maze, start: MazePos, rng: &mut dyn RngCore) -> MazePos {
+        let mut active = vec![start];
+        let mut last = start;
+        maze.0[start] = MazeCell::Cell;
+
+        while !active.is_empty() {
+            let pos = match self.0 {
+                PickStrategy::Newest => active.len() - 1,
+                PickStrategy::Oldest => 0,
+                PickStrategy::Random => pick(rng, active.len()),
+                PickStrategy::Mixed(percent) => {
+                    if rng.gen_range(0_u32, 100) < u32::from(percent) {
+                        active.len() - 1
+                    } else {
+                        pick(rng, active.len())
+                    }
+                }
+            };
+            let room = active[pos];
+
+            let options: Vec<_> = neighbours(maze, room)
+                .into_iter()
+                .filter(|&(next, _)| is_unvisited(maze, next))
+                .collect();
+
+            if options.is_empty() {
+                active.remove(pos);
+            } else {
+                let (next, wall) = options[pick(rng, options.len())];
+                carve_passage(maze, wall, next);
+                active.push(next);
+                last = next;
+            }
+        }
+
+        last
+    }
+}
+
+impl MazeGenerator for Algorithm {
+    fn carve(&self, maze: &mut Maze, start: MazePos, rng: &mut dyn RngCore) -> MazePos {
+        match self {
+            Algorithm::RecursiveBacktracker => RecursiveBacktracker.carve(maze, start, rng),
+            Algorithm::Prim => Prim.carve(maze, start, rng),
+            Algorithm::Kruskal => Kruskal.carve(maze, start, rng),
+            Algorithm::Wilson => Wilson.carve(maze, start, rng),
+            Algorithm::Eller => Eller.carve(maze, start, rng),
+            Algorithm::AldousBroder => AldousBroder.carve(maze, start, rng),
+            Algorithm::HuntAndKill => HuntAndKill.carve(maze, start, rng),
+            Algorithm::GrowingTree(strategy) => GrowingTree(*strategy).carve(maze, start, rng),
+        }
+    }
+}
+
+impl Default for Algorithm {
+    fn default() -> Self {
+        Algorithm::RecursiveBacktracker
+    }
+}
+
+impl fmt::Display for Algorithm {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Algorithm::RecursiveBacktracker => write!(f, "backtracker"),
+            Algorithm::Prim => write!(f, "prim"),
+            Algorithm::Kruskal => write!(f, "kruskal"),
+            Algorithm::Wilson => write!(f, "wilson"),
+            Algorithm::Eller => write!(f, "eller"),
+            Algorithm::AldousBroder => write!(f, "aldous-broder"),
+            Algorithm::HuntAndKill => write!(f, "hunt-and-kill"),
+            Algorithm::GrowingTree(PickStrategy::Newest) => write!(f, "growing-tree-newest"),
+            Algorithm::GrowingTree(PickStrategy::Oldest) => write!(f, "growing-tree-oldest"),
+            Algorithm::GrowingTree(PickStrategy::Random) => write!(f, "growing-tree-random"),
+            Algorithm::GrowingTree(PickStrategy::Mixed(percent)) => {
+                write!(f, "growing-tree-mixed-{}", percent)
+            }
+        }
+    }
+}
+
+/// Parses the names printed by `Display`, e.g. `prim` or
+/// `growing-tree-mixed-75`
+impl FromStr for Algorithm {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        Ok(match name {
+            "backtracker" => Algorithm::RecursiveBacktracker,
+            "prim" => Algorithm::Prim,
+            "kruskal" => Algorithm::Kruskal,
+            "wilson" => Algorithm::Wilson,
+            "eller" => Algorithm::Eller,
+            "aldous-broder" => Algorithm::AldousBroder,
+            "hunt-and-kill" => Algorithm::HuntAndKill,
+            "growing-tree-newest" => Algorithm::GrowingTree(PickStrategy::Newest),
+            "growing-tree-oldest" => Algorithm::GrowingTree(PickStrategy::Oldest),
+            "growing-tree-random" => Algorithm::GrowingTree(PickStrategy::Random),
+            _ if name.starts_with("growing-tree-mixed-") => {
+                match name["growing-tree-mixed-".len()..].parse::<u8>() {
+                    Ok(percent) if percent <= 100 => {
+                        Algorithm::GrowingTree(PickStrategy::Mixed(percent))
+                    }
+                    _ => return Err(format!("{:?} needs a percentage up to 100", name)),
+                }
+            }
+            _ => return Err(format!("{:?} is not a maze algorithm", name)),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::maze_lib::maze_gen::{gen_maze_with, seeded_rng};
+
+    const ALGORITHMS: [Algorithm; 11] = [
+        Algorithm::RecursiveBacktracker,
+        Algorithm::Prim,
+        Algorithm::Kruskal,
+        Algorithm::Wilson,
+        Algorithm::Eller,
+        Algorithm::AldousBroder,
+        Algorithm::HuntAndKill,
+        Algorithm::GrowingTree(PickStrategy::Newest),
+        Algorithm::GrowingTree(PickStrategy::Oldest),
+        Algorithm::GrowingTree(PickStrategy::Random),
+        Algorithm::GrowingTree(PickStrategy::Mixed(50)),
+    ];
+
+    /// Counts the rooms reachable from the start and the passages between
+    /// rooms. A perfect maze reaches every room with one less passage than
+    /// rooms
+    fn rooms_and_passages(maze: &Maze) -> (usize, usize, usize) {
+        let all_rooms = rooms(maze);
+        let mut seen = vec![(1, 1, 1, 1)];
+        let mut stack = vec![(1, 1, 1, 1)];
+        let mut passages = 0;
+
+        while let Some(room) = stack.pop() {
+            for (next, wall) in neighbours(maze, room) {
+                if maze.0[wall].is_traversable() {
+                    if room < next {
+                        passages += 1;
+                    }
+                    if !seen.contains(&next) {
+                        seen.push(next);
+                        stack.push(next);
+                    }
+                }
+            }
+        }
+
+        (all_rooms.len(), seen.len(), passages)
+    }
+
+    #[test]
+    fn every_algorithm_makes_a_perfect_maze() {
+        for algorithm in ALGORITHMS.iter() {
+            for &shape in &[(9, 9, 9, 3), (7, 5, 9, 5), (3, 5, 3, 3)] {
+                let maze = gen_maze_with(algorithm, shape, &mut seeded_rng(7));
+                let (rooms, reached, passages) = rooms_and_passages(&maze);
+
+                assert!(
+                    maze.0.iter().all(|cell| cell != &MazeCell::Unvisited),
+                    "{} left unvisited rooms",
+                    algorithm
+                );
+                assert_eq!(rooms, reached, "{} left rooms unreachable", algorithm);
+                assert_eq!(passages, rooms - 1, "{} made a loop", algorithm);
+                assert_eq!(maze.0[(1, 1, 1, 1)], MazeCell::Start);
+                assert_eq!(maze.0.iter().filter(|c| **c == MazeCell::End).count(), 1);
+            }
+        }
+    }
+
+    #[test]
+    fn algorithms_are_seedable() {
+        for algorithm in ALGORITHMS.iter() {
+            assert_eq!(
+                gen_maze_with(algorithm, (9, 7, 5, 5), &mut seeded_rng(99)).0,
+                gen_maze_with(algorithm, (9, 7, 5, 5), &mut seeded_rng(99)).0
+            );
+        }
+    }
+
+    #[test]
+    fn names_round_trip() {
+        for algorithm in ALGORITHMS.iter() {
+            assert_eq!(algorithm.to_string().parse::<Algorithm>(), Ok(*algorithm));
+        }
+        assert!("growing-tree-mixed-101".parse::<Algorithm>().is_err());
+        assert!("dijkstra".parse::<Algorithm>().is_err());
+    }
+}
@@ -0,0 +1,216 @@
+//! Measures how hard a `Maze` is, so the mazes made by different generators
+//! (or by a change to one) can be compared. Everything is measured on the
+//! cells that can be walked on, where a room joins its neighbours through the
+//! carved walls between them
+use crate::maze_lib::maze_solve::{open_neighbours, solve, solve_with_rotations};
+use crate::{Maze, MazeAxis4, MazeMode, MazePos};
+use ndarray::Array4;
+use serde_derive::{Deserialize, Serialize};
+
+/// The numbers `analyse` measures for a maze
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct MazeReport {
+    /// How many cells long each axis of the maze is
+    pub shape: MazePos,
+    /// The number of cells that can be walked on
+    pub open_cells: usize,
+    /// Cells with only one way out
+    pub dead_ends: usize,
+    /// Cells with 3 or more ways out
+    pub junctions: usize,
+    /// Passages that run between 2 dead ends or junctions without branching
+    pub corridors: usize,
+    /// The number of steps along the longest corridor
+    pub longest_corridor: usize,
+    /// How many walls could be put back without cutting any cell off. A
+    /// perfect maze has none, a braided maze has one for each extra route
+    pub loops: usize,
+    /// The number of steps from Start to End, if End can be reached
+    pub solution_length: Option<usize>,
+    /// The share of open cells that are on the solution (0 to 1)
+    pub solution_share: f64,
+    /// How many times the player has to turn onto another plane to get from
+    /// Start to End, starting in the XY plane as the game does. It is counted
+    /// along the quickest route found by `solve_with_rotations`
+    pub plane_crossings: usize,
+    /// The share of rooms that are part of a corridor (0 to 1). Mazes with
+    /// long winding passages have a high river factor and mazes with lots of
+    /// short dead ends have a low one. This is this project's own measure,
+    /// not the river factor of other maze tools
+    pub river_factor: f64,
+}
+
+/// Measures `maze`
+///
+/// **Panics** if the maze has no Start or End
+pub fn analyse(maze: &Maze) -> MazeReport {
+    let degrees = maze
+        .0
+        .indexed_iter()
+        .filter(|(_, cell)| cell.is_traversable())
+        .map(|(pos, _)| (pos, open_neighbours(maze, pos).count()))
+        .collect::<Vec<_>>();
+    let open_cells = degrees.len();
+    let steps = degrees.iter().map(|&(_, degree)| degree).sum::<usize>() / 2;
+
+    let is_room = |(x, y, z, w): MazePos| x % 2 == 1 && y % 2 == 1 && z % 2 == 1 && w % 2 == 1;
+    let rooms = degrees.iter().filter(|&&(pos, _)| is_room(pos)).count();
+    let corridor_rooms = degrees
+        .iter()
+        .filter(|&&(pos, degree)| is_room(pos) && degree == 2)
+        .count();
+
+    let (corridors, longest_corridor) = corridors(maze, &degrees);
+
+    let solution = solve(maze);
+    // A maze with only one room along W can be played in 3D
+    let mode = if maze.shape().3 > 3 {
+        MazeMode::FourD
+    } else {
+        MazeMode::ThreeD
+    };
+    let plane_crossings = solution
+        .as_ref()
+        .and_then(|route| {
+            solve_with_rotations(maze, route[0], route[route.len() - 1], MazeAxis4::XY, mode)
+        })
+        .map_or(0, |route| {
+            route
+                .windows(2)
+                .filter(|pair| pair[0].1 != pair[1].1)
+                .count()
+        });
+
+    MazeReport {
+        shape: maze.shape(),
+        open_cells,
+        dead_ends: degrees.iter().filter(|&&(_, degree)| degree == 1).count(),
+        junctions: degrees.iter().filter(|&&(_, degree)| degree >= 3).count(),
+        corridors,
+        longest_corridor,
+        loops: (steps + 1).saturating_sub(open_cells),
+        solution_length: solution.as_ref().map(|route| route.len() - 1),
+        solution_share: share(solution.map_or(0, |route| route.len()), open_cells),
+        plane_crossings,
+        river_factor: share(corridor_rooms, rooms),
+    }
+}
+
+/// `part` as a share of `whole`, or 0 if `whole` is empty
+#[allow(clippy::cast_precision_loss)]
+fn share(part: usize, whole: usize) -> f64 {
+    if whole == 0 {
+        0.0
+    } else {
+        part as f64 / whole as f64
+    }
+}
+
+/// Counts the corridors and finds the length of the longest one. `degrees`
+/// holds every open cell with its number of ways out
+fn corridors(maze: &Maze, degrees: &[(MazePos, usize)]) -> (usize, usize) {
+    let mut degree_of = Array4::from_elem(maze.shape(), 0);
+    for &(pos, degree) in degrees {
+        degree_of[pos] = degree;
+    }
+
+    let mut walked = Array4::from_elem(maze.shape(), false);
+    let mut count = 0;
+    let mut longest = 0;
+
+    // Every corridor starts and ends at a cell that isn't part of a corridor
+    for &(end, _) in degrees.iter().filter(|&&(_, degree)| degree != 2) {
+        for first in open_neighbours(maze, end) {
+            if degree_of[first] != 2 || walked[first] {
+                continue;
+            }
+
+            let (mut previous, mut pos, mut length) = (end, first, 1);
+            while degree_of[pos] == 2 && !walked[pos] {
+                walked[pos] = true;
+                /* A cell in a corridor always has another way out */
+                let next = open_neighbours(maze, pos)
+                    .find(|&next| next != previous)
+                    .unwrap();
+                previous = pos;
+                pos = next;
+                length += 1;
+            }
+
+            count += 1;
+            longest = longest.max(length);
+        }
+    }
+    (count, longest)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::maze_lib::maze_gen::{gen_maze_with, seeded_rng};
+    use crate::{Algorithm, MazeCell};
+
+    #[test]
+    fn perfect_maze_numbers() {
+        let maze = gen_maze_with(&Algorithm::Kruskal, (9, 9, 9, 5), &mut seeded_rng(6));
+        let report = analyse(&maze);
+        let route = solve(&maze).unwrap();
+
+        assert_eq!(report.shape, (9, 9, 9, 5));
+        assert_eq!(report.solution_length, Some(route.len() - 1));
+        assert!(report.dead_ends >= 2);
+        assert!(report.solution_share > 0.0 && report.solution_share <= 1.0);
+        assert!(report.river_factor > 0.0 && report.river_factor < 1.0);
+        assert!(report.longest_corridor >= 2);
+
+        // A perfect maze is a tree, so it has one less step between cells
+        // than it has open cells
+        let degrees: usize = maze
+            .0
+            .indexed_iter()
+            .filter(|(_, cell)| cell.is_traversable())
+            .map(|(pos, _)| open_neighbours(&maze, pos).count())
+            .sum();
+        assert_eq!(degrees / 2, report.open_cells - 1);
+        assert_eq!(report.loops, 0);
+    }
+
+    #[test]
+    fn straight_corridor() {
+        let report = analyse(&Maze::corridor());
+        assert_eq!(report.open_cells, 5);
+        assert_eq!(report.dead_ends, 2);
+        assert_eq!(report.junctions, 0);
+        assert_eq!(report.corridors, 1);
+        assert_eq!(report.longest_corridor, 4);
+        assert_eq!(report.loops, 0);
+        assert_eq!(report.solution_length, Some(4));
+        assert_eq!(report.solution_share, 1.0);
+        assert_eq!(report.plane_crossings, 0);
+        assert_eq!(report.river_factor, 1.0 / 3.0);
+    }
+
+    #[test]
+    fn corridor_along_w_needs_one_turn() {
+        let mut maze = Maze::new_empty((3, 3, 3, 7));
+        for cell in maze.0.iter_mut() {
+            *cell = MazeCell::Wall;
+        }
+        for w in 1..6 {
+            maze.0[(1, 1, 1, w)] = MazeCell::Cell;
+        }
+        maze.0[(1, 1, 1, 1)] = MazeCell::Start;
+        maze.0[(1, 1, 1, 5)] = MazeCell::End;
+
+        // Turning back from XY reaches ZW, where W can be walked along
+        assert_eq!(analyse(&maze).plane_crossings, 1);
+    }
+
+    #[test]
+    fn backtracker_winds_more_than_prim() {
+        let shape = (15, 15, 15, 5);
+        let river =
+            |algorithm| analyse(&gen_maze_with(&algorithm, shape, &mut seeded_rng(1))).river_factor;
+        assert!(river(Algorithm::RecursiveBacktracker) > river(Algorithm::Prim));
+    }
+}
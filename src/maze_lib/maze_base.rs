@@ -1,22 +1,29 @@
 //! Contains the basic structures that the 4D game will use.
 //! This is built in stage 1.
 
-use crate::MAZE_SIZE;
-use ndarray::{Array3, ArrayView2, Axis};
+use crate::{Direction, DEFAULT_MAZE_SIZE};
+use ndarray::{Array4, ArrayView2, Axis};
 use std::fmt;
 
-/// `Maze` is a newtype wrapper around `Array3`. This is done for 2 reasons:
+/// A position in the maze in the form `(x, y, z, w)`
+pub type MazePos = (usize, usize, usize, usize);
+
+/// `Maze` is a newtype wrapper around `Array4`. This is done for 2 reasons:
 ///
-/// * It allows me to implement my own methods not in `Array3`
+/// * It allows me to implement my own methods not in `Array4`
 /// * It lets me not type `u8` as the type over and over again
 ///
 /// `Maze` contains 2 methods:
 ///
 /// * `new_empty` equal to `ArrayBase::zeros()` filled with a size of
-/// `MAZE_SIZE` * `view_2_axis` equal to `ArrayBase::subview()` using
-/// `MazeAxis3` instead of `Axis`
-#[derive(Default, Debug)]
-pub struct Maze(pub(crate) Array3<MazeCell>);
+/// `shape` * `view_2_axis` equal to `ArrayBase::subview()` using
+/// `MazeAxis4` instead of `Axis`
+///
+/// A 3D maze is stored as a 4D maze whose W axis is only one room deep. The
+/// size of each axis is chosen when the maze is made and can be read back with
+/// `shape`
+#[derive(Clone, Default, Debug, PartialEq)]
+pub struct Maze(pub(crate) Array4<MazeCell>);
 
 /// `MazeSlice` is a newtype wrapper around `ArrayView2`. This is done for
 /// 2 reasons:
@@ -31,8 +38,15 @@ pub struct Maze(pub(crate) Array3<MazeCell>);
 #[derive(Debug)]
 pub struct MazeSlice<'slice>(pub(crate) ArrayView2<'slice, MazeCell>);
 
-/// As ndarray has many dimensional methods it has an axis type that represents
-/// many axis. It has a from implementation to transform it into an `Axis`
+/// Whether the maze is played in 3 or 4 dimensions
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum MazeMode {
+    ThreeD,
+    FourD,
+}
+
+/// The 3 planes a 3D maze can be cut through. Every `MazeAxis3` is also a
+/// `MazeAxis4`
 #[derive(Clone)]
 pub enum MazeAxis3 {
     XY,
@@ -40,6 +54,18 @@ pub enum MazeAxis3 {
     YZ,
 }
 
+/// The 6 planes a 4D maze can be cut through. The name is the 2 axes that
+/// are shown, the other 2 are fixed by the player's position
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum MazeAxis4 {
+    XY,
+    XZ,
+    XW,
+    YZ,
+    YW,
+    ZW,
+}
+
 #[derive(Clone, PartialEq)]
 pub enum MazeCell {
     Cell,
@@ -52,16 +78,17 @@ pub enum MazeCell {
 }
 
 impl Maze {
-    /// Create a new empty maze with an `Array3` of zeros
-    pub fn new_empty() -> Self {
-        Self(Array3::default((MAZE_SIZE, MAZE_SIZE, MAZE_SIZE)))
+    /// Create a new empty maze with an `Array4` of zeros that is `shape` cells
+    /// long along each axis (walls included)
+    pub fn new_empty(shape: MazePos) -> Self {
+        Self(Array4::default(shape))
     }
 
     #[cfg(test)]
     #[allow(clippy::cast_possible_truncation)]
     // Create a new filled maze for testing
     fn new_prefilled() -> Self {
-        let mut mz = Self::new_empty();
+        let mut mz = Self::new_empty(MazeMode::FourD.default_shape());
         for (pos, cell) in mz.0.iter_mut().enumerate() {
             *cell = MazeCell::Test(pos as u8);
         }
@@ -69,33 +96,100 @@ impl Maze {
         mz
     }
 
-    /// Creates a `MazeSlice` containing a write-only view of 2 of the
-    /// dimensions
+    #[cfg(test)]
+    /// A straight corridor along X through solid wall, from the start at
+    /// (1, 1, 1, 1) to the end 4 steps away
+    pub(crate) fn corridor() -> Self {
+        let mut mz = Self::new_empty((7, 3, 3, 3));
+        for cell in mz.0.iter_mut() {
+            *cell = MazeCell::Wall;
+        }
+        for x in 1..6 {
+            mz.0[(x, 1, 1, 1)] = MazeCell::Cell;
+        }
+        mz.0[(1, 1, 1, 1)] = MazeCell::Start;
+        mz.0[(5, 1, 1, 1)] = MazeCell::End;
+        mz
+    }
+
+    /// Returns the number of cells along each axis of the maze
+    pub fn shape(&self) -> MazePos {
+        let shape = self.0.shape();
+        (shape[0], shape[1], shape[2], shape[3])
+    }
+
+    /// Creates a `MazeSlice` containing a write-only view of the 2 axes in
+    /// `axis4`. The other 2 axes are fixed at their value in `pos`
     ///
     /// **Panics** if `pos` is greater than the size of the maze
-    pub fn view_2_axis(&self, axis3: MazeAxis3, pos: usize) -> MazeSlice {
-        MazeSlice(self.0.index_axis(axis3.into(), pos))
+    pub fn view_2_axis(&self, axis4: MazeAxis4, pos: MazePos) -> MazeSlice {
+        let (first, second) = axis4.hidden_axes();
+        let pos = [pos.0, pos.1, pos.2, pos.3];
+
+        // Remove the higher axis first so the lower one keeps its index
+        MazeSlice(
+            self.0
+                .index_axis(Axis(second), pos[second])
+                .index_axis_move(Axis(first), pos[first]),
+        )
+    }
+
+    /// Returns the position of `MazeCell::Start`
+    ///
+    /// **Panics** if `gen_maze()` has not been used (i.e. Start may not be
+    /// there)
+    pub fn start_pos(&self) -> MazePos {
+        /* If gen_maze is used will always be Some */
+        self.find(MazeCell::Start).unwrap()
     }
 
     /// Returns the position of `MazeCell::End`
     ///
     /// **Panics** if `gen_maze()` has not been used (i.e. End may not be
     /// there)
-    pub fn end_pos(&self) -> (usize, usize, usize) {
-        let end_position = self
+    pub fn end_pos(&self) -> MazePos {
+        /* If gen_maze is used will always be Some */
+        self.find(MazeCell::End).unwrap()
+    }
+
+    /// The position one step from `pos` in `dir`, or `None` if that is
+    /// outside the maze
+    pub fn neighbour(&self, pos: MazePos, dir: Direction) -> Option<MazePos> {
+        let mut coords = [pos.0, pos.1, pos.2, pos.3];
+        let shape = self.0.shape();
+        coords[dir.axis] = if dir.positive {
+            Some(coords[dir.axis] + 1).filter(|&coord| coord < shape[dir.axis])?
+        } else {
+            coords[dir.axis].checked_sub(1)?
+        };
+
+        Some((coords[0], coords[1], coords[2], coords[3]))
+    }
+
+    /// The ways out of `pos` along the axes that can't be seen on `plane`
+    pub fn hidden_openings(&self, plane: MazeAxis4, pos: MazePos) -> Vec<Direction> {
+        let (first, second) = plane.hidden_axes();
+
+        Direction::ALL
+            .iter()
+            .cloned()
+            .filter(|dir| dir.axis == first || dir.axis == second)
+            .filter(|&dir| {
+                self.neighbour(pos, dir)
+                    .map_or(false, |next| self.0[next].is_traversable())
+            })
+            .collect()
+    }
+
+    /// Returns the position of the first `cell` in the maze, if there is one
+    pub fn find(&self, cell: MazeCell) -> Option<MazePos> {
+        let position = self
             .0
             .indexed_iter()
-            .find_map(|(pos, data)| {
-                if data == &MazeCell::End {
-                    Some(pos)
-                } else {
-                    None
-                }
-            }) /* If gen_maze is used will always be Some */
-            .unwrap();
-
-        debug_assert_eq!(self.0.get(end_position), Some(&MazeCell::End));
-        end_position
+            .find_map(|(pos, data)| if data == &cell { Some(pos) } else { None });
+
+        debug_assert!(position.map_or(true, |pos| self.0.get(pos) == Some(&cell)));
+        position
     }
 }
 
@@ -122,6 +216,27 @@ impl MazeCell {
     }
 }
 
+impl MazeMode {
+    /// The shape used when no size is chosen. In `MazeMode::ThreeD` the W axis
+    /// only has room for one layer of cells
+    pub fn default_shape(self) -> MazePos {
+        self.shape((
+            DEFAULT_MAZE_SIZE,
+            DEFAULT_MAZE_SIZE,
+            DEFAULT_MAZE_SIZE,
+            DEFAULT_MAZE_SIZE,
+        ))
+    }
+
+    /// Fits `size` to the mode by flattening the W axis in `MazeMode::ThreeD`
+    pub fn shape(self, size: MazePos) -> MazePos {
+        match self {
+            MazeMode::ThreeD => (size.0, size.1, size.2, 3),
+            MazeMode::FourD => size,
+        }
+    }
+}
+
 impl MazeAxis3 {
     pub fn cycle(&mut self) {
         *self = match self {
@@ -132,12 +247,94 @@ impl MazeAxis3 {
     }
 }
 
-impl From<MazeAxis3> for Axis {
+impl MazeAxis4 {
+    /// Moves onto the next plane. `MazeMode::ThreeD` only cycles through the
+    /// planes that do not contain the W axis
+    pub fn cycle(&mut self, mode: MazeMode) {
+        *self = match (mode, *self) {
+            (MazeMode::ThreeD, MazeAxis4::XY) => MazeAxis4::XZ,
+            (MazeMode::ThreeD, MazeAxis4::XZ) => MazeAxis4::YZ,
+            (MazeMode::ThreeD, _) => MazeAxis4::XY,
+            (MazeMode::FourD, MazeAxis4::XY) => MazeAxis4::XZ,
+            (MazeMode::FourD, MazeAxis4::XZ) => MazeAxis4::XW,
+            (MazeMode::FourD, MazeAxis4::XW) => MazeAxis4::YZ,
+            (MazeMode::FourD, MazeAxis4::YZ) => MazeAxis4::YW,
+            (MazeMode::FourD, MazeAxis4::YW) => MazeAxis4::ZW,
+            (MazeMode::FourD, MazeAxis4::ZW) => MazeAxis4::XY,
+        };
+    }
+
+    /// Moves back onto the previous plane, undoing `cycle`
+    pub fn cycle_back(&mut self, mode: MazeMode) {
+        *self = match (mode, *self) {
+            (MazeMode::ThreeD, MazeAxis4::XZ) => MazeAxis4::XY,
+            (MazeMode::ThreeD, MazeAxis4::YZ) => MazeAxis4::XZ,
+            (MazeMode::ThreeD, _) => MazeAxis4::YZ,
+            (MazeMode::FourD, MazeAxis4::XY) => MazeAxis4::ZW,
+            (MazeMode::FourD, MazeAxis4::XZ) => MazeAxis4::XY,
+            (MazeMode::FourD, MazeAxis4::XW) => MazeAxis4::XZ,
+            (MazeMode::FourD, MazeAxis4::YZ) => MazeAxis4::XW,
+            (MazeMode::FourD, MazeAxis4::YW) => MazeAxis4::YZ,
+            (MazeMode::FourD, MazeAxis4::ZW) => MazeAxis4::YW,
+        };
+    }
+
+    /// The indices of the 2 axes that are shown, smallest first
+    pub fn visible_axes(self) -> (usize, usize) {
+        match self {
+            MazeAxis4::XY => (0, 1),
+            MazeAxis4::XZ => (0, 2),
+            MazeAxis4::XW => (0, 3),
+            MazeAxis4::YZ => (1, 2),
+            MazeAxis4::YW => (1, 3),
+            MazeAxis4::ZW => (2, 3),
+        }
+    }
+
+    /// The indices of the 2 axes that are fixed, smallest first
+    pub fn hidden_axes(self) -> (usize, usize) {
+        match self {
+            MazeAxis4::XY => (2, 3),
+            MazeAxis4::XZ => (1, 3),
+            MazeAxis4::XW => (1, 2),
+            MazeAxis4::YZ => (0, 3),
+            MazeAxis4::YW => (0, 2),
+            MazeAxis4::ZW => (0, 1),
+        }
+    }
+
+    /// The hidden axis the player can still step along without turning. It is
+    /// the lower of the 2 hidden axes, which is the only one in 3D
+    pub fn depth_axis(self) -> usize {
+        self.hidden_axes().0
+    }
+
+    /// Picks the co-ordinates of `pos` that lie on this plane, which are also
+    /// its index in the `MazeSlice`
+    pub fn plane_coords(self, pos: MazePos) -> (usize, usize) {
+        let (first, second) = self.visible_axes();
+        let pos = [pos.0, pos.1, pos.2, pos.3];
+
+        (pos[first], pos[second])
+    }
+
+    /// Replaces the co-ordinates of `pos` that lie on this plane
+    pub fn with_plane_coords(self, pos: MazePos, coords: (usize, usize)) -> MazePos {
+        let (first, second) = self.visible_axes();
+        let mut pos = [pos.0, pos.1, pos.2, pos.3];
+        pos[first] = coords.0;
+        pos[second] = coords.1;
+
+        (pos[0], pos[1], pos[2], pos[3])
+    }
+}
+
+impl From<MazeAxis3> for MazeAxis4 {
     fn from(maze_axis: MazeAxis3) -> Self {
         match maze_axis {
-            MazeAxis3::XY => Self(0),
-            MazeAxis3::XZ => Self(1),
-            MazeAxis3::YZ => Self(2),
+            MazeAxis3::XY => MazeAxis4::XY,
+            MazeAxis3::XZ => MazeAxis4::XZ,
+            MazeAxis3::YZ => MazeAxis4::YZ,
         }
     }
 }
@@ -159,6 +356,36 @@ impl fmt::Debug for MazeAxis3 {
     }
 }
 
+impl fmt::Display for MazeAxis4 {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                MazeAxis4::XY => "XY",
+                MazeAxis4::XZ => "XZ",
+                MazeAxis4::XW => "XW",
+                MazeAxis4::YZ => "YZ",
+                MazeAxis4::YW => "YW",
+                MazeAxis4::ZW => "ZW",
+            }
+        )
+    }
+}
+
+impl fmt::Display for MazeMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                MazeMode::ThreeD => "3D",
+                MazeMode::FourD => "4D",
+            }
+        )
+    }
+}
+
 impl fmt::Debug for MazeCell {
     #[allow(unused_must_use)]
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
@@ -191,37 +418,133 @@ impl Default for MazeCell {
 mod tests {
     use super::*;
 
+    const MAZE_SIZE: usize = DEFAULT_MAZE_SIZE;
     const MAZE_SIZE_2: usize = MAZE_SIZE * MAZE_SIZE;
+    const MAZE_SIZE_3: usize = MAZE_SIZE_2 * MAZE_SIZE;
+
+    #[test]
+    fn hidden_openings() {
+        let mut mz = Maze::new_empty((5, 5, 5, 5));
+        mz.0[(1, 1, 1, 1)] = MazeCell::Cell;
+        mz.0[(1, 1, 2, 1)] = MazeCell::Cell;
+        mz.0[(1, 1, 1, 0)] = MazeCell::End;
+        mz.0[(2, 1, 1, 1)] = MazeCell::Cell;
+
+        assert_eq!(
+            mz.hidden_openings(MazeAxis4::XY, (1, 1, 1, 1)),
+            vec![Direction::new(2, true), Direction::new(3, false)]
+        );
+        // X and Z are on show so the cells next to it along them don't count
+        assert_eq!(
+            mz.hidden_openings(MazeAxis4::XZ, (1, 1, 1, 1)),
+            vec![Direction::new(3, false)]
+        );
+        assert_eq!(
+            mz.hidden_openings(MazeAxis4::ZW, (1, 1, 1, 1)),
+            vec![Direction::new(0, true)]
+        );
+        // W can't go below 0
+        assert_eq!(
+            mz.hidden_openings(MazeAxis4::XY, (1, 1, 1, 0)),
+            vec![Direction::new(3, true)]
+        );
+    }
 
     #[test]
     fn test_empty() {
         assert_eq!(
-            Maze::new_empty().0,
-            Array3::<MazeCell>::default((MAZE_SIZE, MAZE_SIZE, MAZE_SIZE))
+            Maze::new_empty(MazeMode::FourD.default_shape()).0,
+            Array4::<MazeCell>::default((MAZE_SIZE, MAZE_SIZE, MAZE_SIZE, MAZE_SIZE))
+        );
+        assert_eq!(
+            Maze::new_empty(MazeMode::ThreeD.default_shape()).0,
+            Array4::<MazeCell>::default((MAZE_SIZE, MAZE_SIZE, MAZE_SIZE, 3))
         )
     }
 
+    #[test]
+    fn test_shape() {
+        let mz = Maze::new_empty(MazeMode::ThreeD.shape((21, 21, 5, 9)));
+        assert_eq!(mz.shape(), (21, 21, 5, 3));
+
+        let mz_view = mz.view_2_axis(MazeAxis4::YZ, (1, 1, 1, 1));
+        assert_eq!(mz_view.0.shape(), &[21, 5]);
+    }
+
     #[test]
     fn test_make_slice_no_mut() {
         let mz = Maze::new_prefilled();
-        let mz_view = mz.view_2_axis(MazeAxis3::XY, 1);
+        let mz_view = mz.view_2_axis(MazeAxis4::ZW, (1, 0, 0, 0));
 
-        for (mz_num, num) in mz_view.0.iter().zip(MAZE_SIZE_2..=MAZE_SIZE_2 * 2) {
+        for (mz_num, num) in mz_view.0.iter().zip(MAZE_SIZE_3..=MAZE_SIZE_3 * 2) {
             assert_eq!(*mz_num, MazeCell::Test(num as u8));
         }
     }
 
+    #[test]
+    fn test_make_slice_every_plane() {
+        let mz = Maze::new_prefilled();
+        let pos = (1, 2, 3, 4);
+        let strides = [MAZE_SIZE_3, MAZE_SIZE_2, MAZE_SIZE, 1];
+
+        for &axis4 in &[
+            MazeAxis4::XY,
+            MazeAxis4::XZ,
+            MazeAxis4::XW,
+            MazeAxis4::YZ,
+            MazeAxis4::YW,
+            MazeAxis4::ZW,
+        ] {
+            let mz_view = mz.view_2_axis(axis4, pos);
+            let (first, second) = axis4.visible_axes();
+
+            for ((a, b), cell) in mz_view.0.indexed_iter() {
+                let (x, y, z, w) = axis4.with_plane_coords(pos, (a, b));
+                let num = x * strides[0] + y * strides[1] + z * strides[2] + w * strides[3];
+
+                assert_eq!(*cell, MazeCell::Test(num as u8));
+                assert_eq!(axis4.plane_coords((x, y, z, w)), (a, b));
+                assert!(first < second);
+            }
+        }
+    }
+
+    #[test]
+    fn test_cycle() {
+        let mut axis4 = MazeAxis4::XY;
+        for _ in 0..3 {
+            axis4.cycle(MazeMode::ThreeD);
+            assert_eq!(axis4.hidden_axes().1, 3);
+        }
+        assert_eq!(axis4, MazeAxis4::XY);
+
+        for _ in 0..6 {
+            axis4.cycle(MazeMode::FourD);
+        }
+        assert_eq!(axis4, MazeAxis4::XY);
+
+        for &mode in &[MazeMode::ThreeD, MazeMode::FourD] {
+            for _ in 0..6 {
+                let before = axis4;
+                axis4.cycle(mode);
+                axis4.cycle_back(mode);
+                assert_eq!(axis4, before);
+                axis4.cycle(mode);
+            }
+        }
+    }
+
     #[test]
     fn test_cut_to_fit_nopanic() {
         let mz = Maze::new_prefilled();
-        let mz_view = mz.view_2_axis(MazeAxis3::XY, 1);
+        let mz_view = mz.view_2_axis(MazeAxis4::ZW, (1, 0, 0, 0));
 
         for (mz_num, num) in mz_view.cut_to_fit((0, 0), (1, 1)).0.iter().zip(
             [
-                MazeCell::Test(MAZE_SIZE_2 as u8),
-                MazeCell::Test((MAZE_SIZE_2 + 1) as u8),
-                MazeCell::Test((MAZE_SIZE_2 + MAZE_SIZE) as u8),
-                MazeCell::Test((MAZE_SIZE_2 + MAZE_SIZE + 1) as u8),
+                MazeCell::Test(MAZE_SIZE_3 as u8),
+                MazeCell::Test((MAZE_SIZE_3 + 1) as u8),
+                MazeCell::Test((MAZE_SIZE_3 + MAZE_SIZE) as u8),
+                MazeCell::Test((MAZE_SIZE_3 + MAZE_SIZE + 1) as u8),
             ]
             .iter(),
         ) {
@@ -230,10 +553,10 @@ mod tests {
 
         for (mz_num, num) in mz_view.cut_to_fit((1, 1), (1, 1)).0.iter().zip(
             [
-                MazeCell::Test((MAZE_SIZE_2 + MAZE_SIZE + 1) as u8),
-                MazeCell::Test((MAZE_SIZE_2 + MAZE_SIZE + 2) as u8),
-                MazeCell::Test((MAZE_SIZE_2 + MAZE_SIZE * 2 + 1) as u8),
-                MazeCell::Test((MAZE_SIZE_2 + MAZE_SIZE * 2 + 2) as u8),
+                MazeCell::Test((MAZE_SIZE_3 + MAZE_SIZE + 1) as u8),
+                MazeCell::Test((MAZE_SIZE_3 + MAZE_SIZE + 2) as u8),
+                MazeCell::Test((MAZE_SIZE_3 + MAZE_SIZE * 2 + 1) as u8),
+                MazeCell::Test((MAZE_SIZE_3 + MAZE_SIZE * 2 + 2) as u8),
             ]
             .iter(),
         ) {
@@ -244,7 +567,7 @@ mod tests {
             .cut_to_fit((0, 0), (0, 0))
             .0
             .iter()
-            .zip([MazeCell::Test(MAZE_SIZE_2 as u8)].iter())
+            .zip([MazeCell::Test(MAZE_SIZE_3 as u8)].iter())
         {
             assert_eq!(*mz_num, *num)
         }
@@ -259,7 +582,7 @@ mod tests {
     #[test]
     fn test_cut_to_fit_panic() {
         let mz = Maze::new_prefilled();
-        let mz_view = mz.view_2_axis(MazeAxis3::XY, 1);
+        let mz_view = mz.view_2_axis(MazeAxis4::ZW, (1, 0, 0, 0));
 
         assert_eq!(
             mz_view.cut_to_fit((0, 0), (MAZE_SIZE, MAZE_SIZE)).0,
@@ -0,0 +1,846 @@
+//! Saves mazes to files and loads them back. There are 2 encodings of the same
+//! format:
+//!
+//! * A compact binary one that starts with `4DMZ`, followed by the version,
+//! the size, start, end and generator details and then the cells packed 2 to a
+//! byte
+//! * A text one that can be read and edited by hand. It starts with a
+//! `4d-maze <version>` line, then one `key value` line for each detail and
+//! then `cells` followed by every X-Y layer of the maze as a grid of
+//! characters, separated by blank lines
+//!
+//! A `LevelPack` holds several mazes so hand-picked levels can be shipped
+//! together. Its binary encoding starts with `4DMP` and its text encoding is
+//! just every maze's text one after the other
+use crate::{Algorithm, Maze, MazeCell, MazePos};
+use std::{error::Error, fmt, fs, io, path::Path};
+
+/// The version of the format written by this code. Files with any other
+/// version are refused
+pub const FORMAT_VERSION: u16 = 1;
+
+const MAZE_MAGIC: &[u8; 4] = b"4DMZ";
+const PACK_MAGIC: &[u8; 4] = b"4DMP";
+const TEXT_HEADER: &str = "4d-maze";
+
+/// A maze as it is saved, along with the details of how it was made
+#[derive(Clone, Debug, PartialEq)]
+pub struct SavedMaze {
+    pub maze: Maze,
+    pub start: MazePos,
+    pub end: MazePos,
+    /// The name of the level, mostly for level packs
+    pub name: Option<String>,
+    /// The algorithm that carved the maze, if it was generated
+    pub algorithm: Option<Algorithm>,
+    /// The seed the maze was generated from, if it was generated
+    pub seed: Option<u64>,
+    /// The percentage of dead ends that were removed
+    pub braid: u8,
+}
+
+/// Several mazes that are played one after the other
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct LevelPack {
+    pub levels: Vec<SavedMaze>,
+}
+
+/// Which encoding to save in
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Format {
+    Binary,
+    Text,
+}
+
+/// Everything that can go wrong saving or loading a maze
+#[derive(Debug)]
+pub enum MazeFileError {
+    /// The input ended before the maze did
+    Truncated,
+    /// The input isn't a maze (or level pack) file at all
+    NotAMaze,
+    /// The file was written with a different version of the format
+    WrongVersion(u16),
+    /// The input is a maze file but something in it is wrong. The text
+    /// format says which line
+    Malformed(String),
+    /// `MazeCell::Test` is only used while debugging and can't be saved
+    UnsavableCell(MazePos),
+    /// A name with a line break would end early in the text format and one
+    /// over 65535 bytes long does not fit in the binary format
+    UnsavableName(String),
+    /// The file couldn't be read or written
+    Io(io::Error),
+}
+
+impl SavedMaze {
+    /// Wraps a generated maze with no details about how it was made
+    ///
+    /// **Panics** if the maze has no Start or End
+    pub fn new(maze: Maze) -> Self {
+        Self {
+            start: maze.start_pos(),
+            end: maze.end_pos(),
+            maze,
+            name: None,
+            algorithm: None,
+            seed: None,
+            braid: 0,
+        }
+    }
+
+    /// Encodes the maze in the binary format
+    pub fn to_bytes(&self) -> Result<Vec<u8>, MazeFileError> {
+        let mut bytes = MAZE_MAGIC.to_vec();
+        bytes.extend_from_slice(&FORMAT_VERSION.to_le_bytes());
+        for &pos in &[self.maze.shape(), self.start, self.end] {
+            write_pos(&mut bytes, pos);
+        }
+        write_string(&mut bytes, self.name.as_ref().map_or("", String::as_str))?;
+        write_string(
+            &mut bytes,
+            &self
+                .algorithm
+                .map_or_else(String::new, |algorithm| algorithm.to_string()),
+        )?;
+        match self.seed {
+            Some(seed) => {
+                bytes.push(1);
+                bytes.extend_from_slice(&seed.to_le_bytes());
+            }
+            None => bytes.push(0),
+        }
+        bytes.push(self.braid);
+
+        // 2 cells fit in each byte, the first in the low 4 bits
+        let codes = self
+            .maze
+            .0
+            .indexed_iter()
+            .map(|(pos, cell)| cell_code(cell).ok_or(MazeFileError::UnsavableCell(pos)))
+            .collect::<Result<Vec<_>, _>>()?;
+        for pair in codes.chunks(2) {
+            bytes.push(pair[0] | pair.get(1).map_or(0, |code| code << 4));
+        }
+
+        Ok(bytes)
+    }
+
+    /// Decodes a maze in the binary format
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, MazeFileError> {
+        let mut reader = Reader { bytes };
+        reader.header(MAZE_MAGIC)?;
+
+        let shape = reader.pos()?;
+        let start = reader.pos()?;
+        let end = reader.pos()?;
+        let name = Some(reader.string()?).filter(|name| !name.is_empty());
+        let algorithm = reader.string()?;
+        let algorithm = if algorithm.is_empty() {
+            None
+        } else {
+            Some(algorithm.parse().map_err(MazeFileError::Malformed)?)
+        };
+        let seed = match reader.u8()? {
+            0 => None,
+            1 => Some(reader.u64()?),
+            flag => return Err(malformed(format!("{} is not a seed flag", flag))),
+        };
+        let braid = reader.u8()?;
+
+        let len = cell_count(shape)?;
+        let packed = reader.take((len + 1) / 2)?;
+        let mut cells = Vec::with_capacity(len);
+        for (index, byte) in packed.iter().enumerate() {
+            cells.push(code_cell(byte & 0xF)?);
+            if index * 2 + 1 < len {
+                cells.push(code_cell(byte >> 4)?);
+            }
+        }
+        if !reader.bytes.is_empty() {
+            return Err(malformed("there is data after the cells"));
+        }
+
+        Self::build(shape, cells, start, end, name, algorithm, seed, braid)
+    }
+
+    /// Encodes the maze in the text format
+    pub fn to_text(&self) -> Result<String, MazeFileError> {
+        let (size_x, size_y, size_z, size_w) = self.maze.shape();
+        let mut text = format!("{} {}\n", TEXT_HEADER, FORMAT_VERSION);
+        if let Some(name) = &self.name {
+            if name.contains(|ch| ch == '\n' || ch == '\r') {
+                return Err(MazeFileError::UnsavableName(name.clone()));
+            }
+            text += &format!("name {}\n", name);
+        }
+        text += &format!("size {} {} {} {}\n", size_x, size_y, size_z, size_w);
+        text += &format!("start {}\n", pos_text(self.start));
+        text += &format!("end {}\n", pos_text(self.end));
+        if let Some(algorithm) = self.algorithm {
+            text += &format!("algorithm {}\n", algorithm);
+        }
+        if let Some(seed) = self.seed {
+            text += &format!("seed {}\n", seed);
+        }
+        text += &format!("braid {}\n", self.braid);
+        text += "cells\n";
+
+        for w in 0..size_w {
+            for z in 0..size_z {
+                if z > 0 || w > 0 {
+                    text.push('\n');
+                }
+                for y in 0..size_y {
+                    for x in 0..size_x {
+                        let pos = (x, y, z, w);
+                        let ch = cell_char(&self.maze.0[pos])
+                            .ok_or(MazeFileError::UnsavableCell(pos))?;
+                        text.push(ch);
+                    }
+                    text.push('\n');
+                }
+            }
+        }
+
+        Ok(text)
+    }
+
+    /// Decodes a maze in the text format
+    pub fn from_text(text: &str) -> Result<Self, MazeFileError> {
+        let mut lines = text
+            .lines()
+            .enumerate()
+            .map(|(index, line)| (index + 1, line));
+
+        let (_, header) = lines.next().ok_or(MazeFileError::Truncated)?;
+        let mut header = header.split(' ');
+        if header.next() != Some(TEXT_HEADER) {
+            return Err(MazeFileError::NotAMaze);
+        }
+        let version = header
+            .next()
+            .and_then(|version| version.parse().ok())
+            .ok_or_else(|| malformed("line 1: the version is missing"))?;
+        if version != FORMAT_VERSION {
+            return Err(MazeFileError::WrongVersion(version));
+        }
+
+        let (mut shape, mut start, mut end) = (None, None, None);
+        let (mut name, mut algorithm, mut seed, mut braid) = (None, None, None, 0);
+        loop {
+            let (number, untrimmed) = lines.next().ok_or(MazeFileError::Truncated)?;
+            let line = untrimmed.trim_end();
+            let (key, value) = match line.find(' ') {
+                Some(space) => (&line[..space], &line[space + 1..]),
+                None => (line, ""),
+            };
+            let bad_value = || {
+                malformed(format!(
+                    "line {}: {:?} is not a valid {}",
+                    number, value, key
+                ))
+            };
+
+            match key {
+                "cells" => break,
+                // Spaces at the end of a name are part of it
+                "name" => name = Some(untrimmed.get(key.len() + 1..).unwrap_or("").to_string()),
+                "size" => shape = Some(parse_pos(value).ok_or_else(bad_value)?),
+                "start" => start = Some(parse_pos(value).ok_or_else(bad_value)?),
+                "end" => end = Some(parse_pos(value).ok_or_else(bad_value)?),
+                "algorithm" => algorithm = Some(value.parse().map_err(|_| bad_value())?),
+                "seed" => seed = Some(value.parse().map_err(|_| bad_value())?),
+                "braid" => braid = value.parse().map_err(|_| bad_value())?,
+                _ => return Err(malformed(format!("line {}: unknown key {:?}", number, key))),
+            }
+        }
+
+        let shape = shape.ok_or_else(|| malformed("the size is missing"))?;
+        let start = start.ok_or_else(|| malformed("the start is missing"))?;
+        let end = end.ok_or_else(|| malformed("the end is missing"))?;
+
+        // The layers are read in file order and put back in index order below.
+        // Nothing is reserved from the size until the rows have been checked
+        // against it, so a huge size can't run out of memory
+        let (size_x, size_y, size_z, size_w) = shape;
+        cell_count(shape)?;
+        let row_count = cell_count((1, size_y, size_z, size_w))?;
+        let rows: Vec<_> = lines
+            .map(|(number, line)| (number, line.trim_end()))
+            .filter(|(_, line)| !line.is_empty())
+            .collect();
+        if rows.len() < row_count {
+            // A short last line is where the input was cut off
+            return match rows.last() {
+                Some((_, line)) if line.chars().count() < size_x => Err(MazeFileError::Truncated),
+                None => Err(MazeFileError::Truncated),
+                Some(_) => Err(malformed(format!(
+                    "the size needs {} rows but there are {}",
+                    row_count,
+                    rows.len()
+                ))),
+            };
+        } else if let Some((number, _)) = rows.get(row_count) {
+            return Err(malformed(format!(
+                "line {}: there are too many rows",
+                number
+            )));
+        }
+
+        let mut layers = Vec::new();
+        for (number, line) in rows {
+            if line.chars().count() != size_x {
+                return Err(malformed(format!(
+                    "line {}: the row should be {} cells long",
+                    number, size_x
+                )));
+            }
+            for ch in line.chars() {
+                layers.push(char_cell(ch).ok_or_else(|| {
+                    malformed(format!("line {}: {:?} is not a cell", number, ch))
+                })?);
+            }
+        }
+
+        // `layers` is ordered w, z, y, x but the maze is indexed x, y, z, w
+        let mut cells = Vec::with_capacity(layers.len());
+        for x in 0..size_x {
+            for y in 0..size_y {
+                for z in 0..size_z {
+                    for w in 0..size_w {
+                        cells.push(layers[((w * size_z + z) * size_y + y) * size_x + x].clone());
+                    }
+                }
+            }
+        }
+
+        Self::build(shape, cells, start, end, name, algorithm, seed, braid)
+    }
+
+    /// Loads a maze saved in either format
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, MazeFileError> {
+        let bytes = fs::read(path)?;
+        if bytes.starts_with(MAZE_MAGIC) {
+            Self::from_bytes(&bytes)
+        } else {
+            Self::from_text(&String::from_utf8(bytes).map_err(|_| MazeFileError::NotAMaze)?)
+        }
+    }
+
+    /// Saves the maze in `format`
+    pub fn save<P: AsRef<Path>>(&self, path: P, format: Format) -> Result<(), MazeFileError> {
+        let bytes = match format {
+            Format::Binary => self.to_bytes()?,
+            Format::Text => self.to_text()?.into_bytes(),
+        };
+        Ok(fs::write(path, bytes)?)
+    }
+
+    /// Puts a loaded maze together, checking the cells agree with the rest
+    #[allow(clippy::too_many_arguments)]
+    fn build(
+        shape: MazePos,
+        cells: Vec<MazeCell>,
+        start: MazePos,
+        end: MazePos,
+        name: Option<String>,
+        algorithm: Option<Algorithm>,
+        seed: Option<u64>,
+        braid: u8,
+    ) -> Result<Self, MazeFileError> {
+        if shape.0 < 3 || shape.1 < 3 || shape.2 < 3 || shape.3 < 3 {
+            return Err(malformed("every axis needs to be at least 3 cells long"));
+        }
+        let maze = Maze(
+            ndarray::Array4::from_shape_vec(shape, cells)
+                .map_err(|_| malformed("the cells don't fit the size"))?,
+        );
+        if maze.0.get(start) != Some(&MazeCell::Start) {
+            return Err(malformed(format!(
+                "there is no start at {}",
+                pos_text(start)
+            )));
+        }
+        if maze.0.get(end) != Some(&MazeCell::End) {
+            return Err(malformed(format!("there is no end at {}", pos_text(end))));
+        }
+        if braid > 100 {
+            return Err(malformed(format!(
+                "{}% of dead ends can't be removed",
+                braid
+            )));
+        }
+
+        Ok(Self {
+            maze,
+            start,
+            end,
+            name,
+            algorithm,
+            seed,
+            braid,
+        })
+    }
+}
+
+impl LevelPack {
+    /// Encodes every level in the binary format
+    #[allow(clippy::cast_possible_truncation)]
+    pub fn to_bytes(&self) -> Result<Vec<u8>, MazeFileError> {
+        let mut bytes = PACK_MAGIC.to_vec();
+        bytes.extend_from_slice(&FORMAT_VERSION.to_le_bytes());
+        bytes.extend_from_slice(&(self.levels.len() as u32).to_le_bytes());
+        for level in &self.levels {
+            let level = level.to_bytes()?;
+            bytes.extend_from_slice(&(level.len() as u32).to_le_bytes());
+            bytes.extend_from_slice(&level);
+        }
+        Ok(bytes)
+    }
+
+    /// Decodes a level pack in the binary format
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, MazeFileError> {
+        let mut reader = Reader { bytes };
+        reader.header(PACK_MAGIC)?;
+
+        let count = reader.u32()?;
+        let mut levels = Vec::new();
+        for _ in 0..count {
+            let len = reader.u32()? as usize;
+            levels.push(SavedMaze::from_bytes(reader.take(len)?)?);
+        }
+        if !reader.bytes.is_empty() {
+            return Err(malformed("there is data after the last level"));
+        }
+        Ok(Self { levels })
+    }
+
+    /// Encodes every level in the text format, one after the other
+    pub fn to_text(&self) -> Result<String, MazeFileError> {
+        let levels = self
+            .levels
+            .iter()
+            .map(SavedMaze::to_text)
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(levels.join("\n"))
+    }
+
+    /// Decodes a level pack in the text format. Each level starts at a
+    /// `4d-maze` line
+    pub fn from_text(text: &str) -> Result<Self, MazeFileError> {
+        if !text.starts_with(TEXT_HEADER) {
+            return Err(MazeFileError::NotAMaze);
+        }
+
+        let mut starts = text
+            .match_indices(&format!("\n{} ", TEXT_HEADER))
+            .map(|(index, _)| index + 1)
+            .collect::<Vec<_>>();
+        starts.insert(0, 0);
+        starts.push(text.len());
+
+        let levels = starts
+            .windows(2)
+            .map(|range| SavedMaze::from_text(&text[range[0]..range[1]]))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Self { levels })
+    }
+
+    /// Loads a level pack saved in either format. A single maze file is
+    /// loaded as a pack with one level
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, MazeFileError> {
+        let bytes = fs::read(path)?;
+        if bytes.starts_with(PACK_MAGIC) {
+            Self::from_bytes(&bytes)
+        } else if bytes.starts_with(MAZE_MAGIC) {
+            Ok(Self {
+                levels: vec![SavedMaze::from_bytes(&bytes)?],
+            })
+        } else {
+            Self::from_text(&String::from_utf8(bytes).map_err(|_| MazeFileError::NotAMaze)?)
+        }
+    }
+
+    /// Saves every level in `format`
+    pub fn save<P: AsRef<Path>>(&self, path: P, format: Format) -> Result<(), MazeFileError> {
+        let bytes = match format {
+            Format::Binary => self.to_bytes()?,
+            Format::Text => self.to_text()?.into_bytes(),
+        };
+        Ok(fs::write(path, bytes)?)
+    }
+}
+
+/// Reads the binary format from the front of `bytes`
+pub(crate) struct Reader<'bytes> {
+    pub(crate) bytes: &'bytes [u8],
+}
+
+impl<'bytes> Reader<'bytes> {
+    pub(crate) fn take(&mut self, len: usize) -> Result<&'bytes [u8], MazeFileError> {
+        if self.bytes.len() < len {
+            return Err(MazeFileError::Truncated);
+        }
+        let (taken, rest) = self.bytes.split_at(len);
+        self.bytes = rest;
+        Ok(taken)
+    }
+
+    /// Checks the magic number and the version
+    fn header(&mut self, magic: &[u8; 4]) -> Result<(), MazeFileError> {
+        match self.version(magic)? {
+            FORMAT_VERSION => Ok(()),
+            version => Err(MazeFileError::WrongVersion(version)),
+        }
+    }
+
+    /// Checks the magic number and returns the version that follows it
+    pub(crate) fn version(&mut self, magic: &[u8; 4]) -> Result<u16, MazeFileError> {
+        if self.take(4).map_err(|_| MazeFileError::NotAMaze)? != magic {
+            return Err(MazeFileError::NotAMaze);
+        }
+        self.u16()
+    }
+
+    pub(crate) fn u8(&mut self) -> Result<u8, MazeFileError> {
+        Ok(self.take(1)?[0])
+    }
+
+    pub(crate) fn u16(&mut self) -> Result<u16, MazeFileError> {
+        let bytes = self.take(2)?;
+        Ok(u16::from(bytes[0]) | u16::from(bytes[1]) << 8)
+    }
+
+    pub(crate) fn u32(&mut self) -> Result<u32, MazeFileError> {
+        let bytes = self.take(4)?;
+        Ok(bytes
+            .iter()
+            .rev()
+            .fold(0, |value, &byte| value << 8 | u32::from(byte)))
+    }
+
+    pub(crate) fn u64(&mut self) -> Result<u64, MazeFileError> {
+        let bytes = self.take(8)?;
+        Ok(bytes
+            .iter()
+            .rev()
+            .fold(0, |value, &byte| value << 8 | u64::from(byte)))
+    }
+
+    pub(crate) fn pos(&mut self) -> Result<MazePos, MazeFileError> {
+        Ok((
+            self.u32()? as usize,
+            self.u32()? as usize,
+            self.u32()? as usize,
+            self.u32()? as usize,
+        ))
+    }
+
+    pub(crate) fn string(&mut self) -> Result<String, MazeFileError> {
+        let len = usize::from(self.u16()?);
+        String::from_utf8(self.take(len)?.to_vec()).map_err(|_| malformed("a string is not UTF-8"))
+    }
+}
+
+/// Writes the 4 co-ordinates of `pos`
+#[allow(clippy::cast_possible_truncation)]
+pub(crate) fn write_pos(bytes: &mut Vec<u8>, pos: MazePos) {
+    for &coord in &[pos.0, pos.1, pos.2, pos.3] {
+        bytes.extend_from_slice(&(coord as u32).to_le_bytes());
+    }
+}
+
+/// Writes `string` with its length in front
+///
+/// Returns `MazeFileError::UnsavableName` if it is over 65535 bytes long
+#[allow(clippy::cast_possible_truncation)]
+pub(crate) fn write_string(bytes: &mut Vec<u8>, string: &str) -> Result<(), MazeFileError> {
+    if string.len() > usize::from(u16::max_value()) {
+        return Err(MazeFileError::UnsavableName(string.to_string()));
+    }
+    bytes.extend_from_slice(&(string.len() as u16).to_le_bytes());
+    bytes.extend_from_slice(string.as_bytes());
+    Ok(())
+}
+
+/// The number of cells in a maze of `shape`, checking it can't overflow
+fn cell_count(shape: MazePos) -> Result<usize, MazeFileError> {
+    shape
+        .0
+        .checked_mul(shape.1)
+        .and_then(|len| len.checked_mul(shape.2))
+        .and_then(|len| len.checked_mul(shape.3))
+        .ok_or_else(|| malformed("the maze is too big"))
+}
+
+pub(crate) fn malformed<S: Into<String>>(reason: S) -> MazeFileError {
+    MazeFileError::Malformed(reason.into())
+}
+
+/// The binary code of a cell, the same number its `Debug` output uses
+fn cell_code(cell: &MazeCell) -> Option<u8> {
+    match cell {
+        MazeCell::OuterWall => Some(0),
+        MazeCell::Cell => Some(1),
+        MazeCell::Wall => Some(2),
+        MazeCell::Start => Some(3),
+        MazeCell::Unvisited => Some(4),
+        MazeCell::End => Some(5),
+        MazeCell::Test(_) => None,
+    }
+}
+
+fn code_cell(code: u8) -> Result<MazeCell, MazeFileError> {
+    match code {
+        0 => Ok(MazeCell::OuterWall),
+        1 => Ok(MazeCell::Cell),
+        2 => Ok(MazeCell::Wall),
+        3 => Ok(MazeCell::Start),
+        4 => Ok(MazeCell::Unvisited),
+        5 => Ok(MazeCell::End),
+        _ => Err(malformed(format!("{} is not a cell", code))),
+    }
+}
+
+/// The character a cell is written as in the text format
+fn cell_char(cell: &MazeCell) -> Option<char> {
+    match cell {
+        MazeCell::OuterWall => Some('#'),
+        MazeCell::Wall => Some('+'),
+        MazeCell::Cell => Some('.'),
+        MazeCell::Start => Some('S'),
+        MazeCell::End => Some('E'),
+        MazeCell::Unvisited => Some('?'),
+        MazeCell::Test(_) => None,
+    }
+}
+
+fn char_cell(ch: char) -> Option<MazeCell> {
+    match ch {
+        '#' => Some(MazeCell::OuterWall),
+        '+' => Some(MazeCell::Wall),
+        '.' => Some(MazeCell::Cell),
+        'S' => Some(MazeCell::Start),
+        'E' => Some(MazeCell::End),
+        '?' => Some(MazeCell::Unvisited),
+        _ => None,
+    }
+}
+
+fn pos_text((x, y, z, w): MazePos) -> String {
+    format!("{} {} {} {}", x, y, z, w)
+}
+
+fn parse_pos(text: &str) -> Option<MazePos> {
+    let coords = text
+        .split(' ')
+        .map(|coord| coord.parse().ok())
+        .collect::<Option<Vec<usize>>>()?;
+    match coords.as_slice() {
+        [x, y, z, w] => Some((*x, *y, *z, *w)),
+        _ => None,
+    }
+}
+
+impl fmt::Display for MazeFileError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            MazeFileError::Truncated => write!(f, "The maze file ends too early"),
+            MazeFileError::NotAMaze => write!(f, "This is not a maze file"),
+            MazeFileError::WrongVersion(version) => write!(
+                f,
+                "The maze file is version {} but only version {} can be read",
+                version, FORMAT_VERSION
+            ),
+            MazeFileError::Malformed(reason) => write!(f, "The maze file is broken: {}", reason),
+            MazeFileError::UnsavableCell(pos) => write!(f, "The cell at {:?} can't be saved", pos),
+            MazeFileError::UnsavableName(name) if name.contains(|ch| ch == '\n' || ch == '\r') => {
+                write!(
+                    f,
+                    "The name {:?} can't be saved with a line break in it",
+                    name
+                )
+            }
+            MazeFileError::UnsavableName(name) => write!(
+                f,
+                "A name {} bytes long can't be saved, the most is 65535",
+                name.len()
+            ),
+            MazeFileError::Io(error) => write!(f, "{}", error),
+        }
+    }
+}
+
+impl Error for MazeFileError {}
+
+impl From<io::Error> for MazeFileError {
+    fn from(error: io::Error) -> Self {
+        MazeFileError::Io(error)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::maze_lib::maze_gen::{gen_maze_with, seeded_rng};
+
+    fn saved(seed: u64) -> SavedMaze {
+        let mut saved = SavedMaze::new(gen_maze_with(
+            &Algorithm::Wilson,
+            (9, 7, 5, 3),
+            &mut seeded_rng(seed),
+        ));
+        saved.name = Some(format!("Level {}", seed));
+        saved.algorithm = Some(Algorithm::Wilson);
+        saved.seed = Some(seed);
+        saved
+    }
+
+    #[test]
+    fn binary_round_trip() {
+        let maze = saved(1);
+        let bytes = maze.to_bytes().unwrap();
+        assert_eq!(&bytes[..4], b"4DMZ");
+        assert_eq!(SavedMaze::from_bytes(&bytes).unwrap(), maze);
+
+        let plain = SavedMaze::new(maze.maze.clone());
+        assert_eq!(
+            SavedMaze::from_bytes(&plain.to_bytes().unwrap()).unwrap(),
+            plain
+        );
+    }
+
+    #[test]
+    fn text_round_trip() {
+        let maze = saved(2);
+        let text = maze.to_text().unwrap();
+        assert!(text.starts_with("4d-maze 1\nname Level 2\nsize 9 7 5 3\n"));
+        assert_eq!(SavedMaze::from_text(&text).unwrap(), maze);
+    }
+
+    #[test]
+    fn names_round_trip() {
+        let mut maze = saved(9);
+        for name in &["  spaces at both ends  ", "", "tab\tin the middle"] {
+            maze.name = Some(name.to_string());
+            assert_eq!(
+                SavedMaze::from_text(&maze.to_text().unwrap()).unwrap(),
+                maze
+            );
+        }
+
+        // A line break would end the name early, or start a new level in a
+        // pack
+        for name in &["two\nlines", "\n4d-maze 1", "carriage\rreturn"] {
+            maze.name = Some(name.to_string());
+            match maze.to_text() {
+                Err(MazeFileError::UnsavableName(_)) => (),
+                other => panic!("expected UnsavableName, got {:?}", other),
+            }
+        }
+
+        // Too long for the length in front of it, even though cutting it at
+        // 65535 bytes would split a character
+        maze.name = Some("é".repeat(40_000));
+        match maze.to_bytes() {
+            Err(MazeFileError::UnsavableName(_)) => (),
+            other => panic!("expected UnsavableName, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn pack_round_trip() {
+        let pack = LevelPack {
+            levels: vec![saved(3), saved(4), saved(5)],
+        };
+        assert_eq!(
+            LevelPack::from_bytes(&pack.to_bytes().unwrap()).unwrap(),
+            pack
+        );
+        assert_eq!(
+            LevelPack::from_text(&pack.to_text().unwrap()).unwrap(),
+            pack
+        );
+    }
+
+    #[test]
+    fn truncated() {
+        let bytes = saved(6).to_bytes().unwrap();
+        for len in &[5, 20, bytes.len() - 1] {
+            match SavedMaze::from_bytes(&bytes[..*len]) {
+                Err(MazeFileError::Truncated) => (),
+                other => panic!("expected Truncated, got {:?}", other),
+            }
+        }
+
+        let text = saved(6).to_text().unwrap();
+        match SavedMaze::from_text(&text[..text.len() / 2]) {
+            Err(MazeFileError::Truncated) => (),
+            other => panic!("expected Truncated, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn wrong_version() {
+        let mut bytes = saved(7).to_bytes().unwrap();
+        bytes[4] = 9;
+        match SavedMaze::from_bytes(&bytes) {
+            Err(MazeFileError::WrongVersion(9)) => (),
+            other => panic!("expected WrongVersion, got {:?}", other),
+        }
+
+        let text = saved(7)
+            .to_text()
+            .unwrap()
+            .replacen("4d-maze 1", "4d-maze 2", 1);
+        match SavedMaze::from_text(&text) {
+            Err(MazeFileError::WrongVersion(2)) => (),
+            other => panic!("expected WrongVersion, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn malformed_input() {
+        match SavedMaze::from_bytes(b"PNG!1234") {
+            Err(MazeFileError::NotAMaze) => (),
+            other => panic!("expected NotAMaze, got {:?}", other),
+        }
+
+        let text = saved(8).to_text().unwrap();
+        let broken = [
+            text.replacen("size 9 7 5 3", "size 9 7 five 3", 1),
+            text.replacen("S", ".", 1),
+            text.replacen("#########\n", "####X####\n", 1),
+            text.replacen("#########\n", "########\n", 1),
+            text.replacen("braid 0", "colour red", 1),
+            text.clone() + "#########\n",
+            // A size far bigger than the rows given
+            text.replacen("size 9 7 5 3", "size 9 1000000000 1000000000 3", 1),
+        ];
+        for text in &broken {
+            match SavedMaze::from_text(text) {
+                Err(MazeFileError::Malformed(_)) => (),
+                other => panic!("expected Malformed, got {:?}", other),
+            }
+        }
+
+        let mut bytes = saved(8).to_bytes().unwrap();
+        let last = bytes.len() - 1;
+        bytes[last] = 0xFF;
+        match SavedMaze::from_bytes(&bytes) {
+            Err(MazeFileError::Malformed(_)) => (),
+            other => panic!("expected Malformed, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_cells_cant_be_saved() {
+        let mut maze = saved(9);
+        maze.maze.0[(1, 1, 1, 1)] = MazeCell::Test(7);
+        match maze.to_bytes() {
+            Err(MazeFileError::UnsavableCell((1, 1, 1, 1))) => (),
+            other => panic!("expected UnsavableCell, got {:?}", other),
+        }
+    }
+}
@@ -1,191 +1,419 @@
 //! Generates the maze
-use crate::{Maze, MazeCell, MAZE_SIZE};
-use rand::{distributions::Uniform, thread_rng, Rng};
+use crate::maze_lib::maze_placement::{ExitPlacement, StartPlacement};
+use crate::{Maze, MazeCell, MazePos};
+use rand::{distributions::Uniform, prng::ChaChaRng, thread_rng, Rng, RngCore, SeedableRng};
 
-pub fn gen_maze() -> Maze {
-    const MAZE_SIZE_MINUS_ONE: usize = MAZE_SIZE - 1;
+/// The 8 directions the generator can carve in, as an axis index and whether
+/// the step is towards the positive end of that axis
+pub(crate) const DIRECTIONS: [(usize, bool); 8] = [
+    (0, true),
+    (0, false),
+    (1, true),
+    (1, false),
+    (2, true),
+    (2, false),
+    (3, true),
+    (3, false),
+];
 
-    let mut maze = Maze::new_empty();
-    let mut mz = maze.0.view_mut();
+/// Generates a maze of `shape` cells using recursive backtracking. The maze
+/// is carved in all 8 directions, but a 3D maze (see `MazeMode::shape`) is
+/// only one room deep along W so only 6 of them are ever taken
+///
+/// **Panics** if any axis of `shape` is shorter than 3 cells (i.e. there is
+/// no room for the start)
+pub fn gen_maze(shape: MazePos) -> Maze {
+    gen_maze_with_rng(shape, &mut thread_rng())
+}
 
-    let mut visited: Vec<(usize, usize, usize)> = Vec::with_capacity(MAZE_SIZE.pow(3) / 2);
-    let mut tot_visited: Vec<(usize, usize, usize)> = Vec::with_capacity(MAZE_SIZE.pow(3) / 2);
-    let mut pos = (1, 1, 1);
+/// Generates the same maze every time it is given the same `seed` and
+/// `shape`, on every platform
+///
+/// **Panics** under the same conditions as `gen_maze()`
+pub fn gen_maze_with_seed(shape: MazePos, seed: u64) -> Maze {
+    gen_maze_with_rng(shape, &mut seeded_rng(seed))
+}
 
-    for (row_outer_pos, mut row_outer) in mz.outer_iter_mut().enumerate() {
-        match row_outer_pos {
-            0 | MAZE_SIZE_MINUS_ONE => {
-                for cell in row_outer.iter_mut() {
-                    *cell = MazeCell::OuterWall;
-                }
-            }
-            _ if row_outer_pos % 2 == 0 => {
-                for (row_inner_pos, mut row_inner) in row_outer.outer_iter_mut().enumerate() {
-                    match row_inner_pos {
-                        0 | MAZE_SIZE_MINUS_ONE => {
-                            for cell in row_inner.iter_mut() {
-                                *cell = MazeCell::OuterWall;
-                            }
-                        }
-                        _ => {
-                            for (cell_pos, cell) in row_inner.iter_mut().enumerate() {
-                                match cell_pos {
-                                    0 | MAZE_SIZE_MINUS_ONE => {
-                                        *cell = MazeCell::OuterWall;
-                                    }
-                                    _ => {
-                                        *cell = MazeCell::Wall;
-                                    }
-                                }
-                            }
-                        }
-                    }
-                }
-            }
-            _ => {
-                for (row_inner_pos, mut row_inner) in row_outer.outer_iter_mut().enumerate() {
-                    match row_inner_pos {
-                        0 | MAZE_SIZE_MINUS_ONE => {
-                            for cell in row_inner.iter_mut() {
-                                *cell = MazeCell::OuterWall;
-                            }
-                        }
-                        _ if row_inner_pos % 2 == 0 => {
-                            for (cell_pos, cell) in row_inner.iter_mut().enumerate() {
-                                match cell_pos {
-                                    0 | MAZE_SIZE_MINUS_ONE => {
-                                        *cell = MazeCell::OuterWall;
-                                    }
-                                    _ => {
-                                        *cell = MazeCell::Wall;
-                                    }
-                                }
-                            }
-                        }
-                        _ => {
-                            for (cell_pos, cell) in row_inner.iter_mut().enumerate() {
-                                match cell_pos {
-                                    0 | MAZE_SIZE_MINUS_ONE => {
-                                        *cell = MazeCell::OuterWall;
-                                    }
-                                    _ if cell_pos % 2 == 0 => {
-                                        *cell = MazeCell::Wall;
-                                    }
-                                    _ => {}
-                                }
-                            }
-                        }
-                    }
-                }
-            }
-        }
+/// Creates the random number generator `gen_maze_with_seed()` uses.
+/// `ChaChaRng` is used because its output does not depend on the platform
+pub fn seeded_rng(seed: u64) -> ChaChaRng {
+    let mut key = [0; 32];
+    for (byte, pos) in key.iter_mut().zip(0..8) {
+        *byte = (seed >> (pos * 8)) as u8;
     }
 
-    *mz.get_mut(pos).unwrap() = MazeCell::Start;
-    visited.push(pos);
-    tot_visited.push(pos);
-
-    let mut rng = thread_rng();
-    let range = Uniform::new(0, 6);
-
-    loop {
-        enum AxisDir {
-            XPos,
-            XNeg,
-            YPos,
-            YNeg,
-            ZPos,
-            ZNeg,
-        }
+    ChaChaRng::from_seed(key)
+}
+
+/// Generates a maze of `shape` cells with the directions picked by `rng`
+///
+/// **Panics** under the same conditions as `gen_maze()`
+pub fn gen_maze_with_rng<R: Rng>(shape: MazePos, rng: &mut R) -> Maze {
+    gen_maze_with(&RecursiveBacktracker, shape, rng)
+}
+
+/// The choices for a maze that don't depend on the generator
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct MazeOptions {
+    pub start: StartPlacement,
+    pub exit: ExitPlacement,
+    /// The percentage of dead ends `braid()` removes (0 keeps the maze
+    /// perfect)
+    pub braid: u8,
+}
+
+/// Generates a maze of `shape` cells, carving the passages with `generator`.
+/// The start is always `(1, 1, 1, 1)` and the end is the last room the
+/// generator carved
+///
+/// **Panics** under the same conditions as `gen_maze()`
+pub fn gen_maze_with<G: MazeGenerator + ?Sized, R: Rng>(
+    generator: &G,
+    shape: MazePos,
+    rng: &mut R,
+) -> Maze {
+    gen_maze_with_options(generator, shape, &MazeOptions::default(), rng)
+}
+
+/// Generates a maze of `shape` cells, carving the passages with `generator`
+/// and placing the start and end as `options` says
+///
+/// **Panics** under the same conditions as `gen_maze()` or if the start
+/// given in `options` isn't a room of the maze
+pub fn gen_maze_with_options<G: MazeGenerator + ?Sized, R: Rng>(
+    generator: &G,
+    shape: MazePos,
+    options: &MazeOptions,
+    rng: &mut R,
+) -> Maze {
+    assert!(
+        shape.0 >= 3 && shape.1 >= 3 && shape.2 >= 3 && shape.3 >= 3,
+        "Every axis of the maze needs to be at least 3 cells long"
+    );
 
-        let (x, y, z) = pos;
+    let mut maze = Maze::new_empty(shape);
+    let shape = [shape.0, shape.1, shape.2, shape.3];
 
-        let x1 = x.checked_sub(2).is_none();
-        let y1 = y.checked_sub(2).is_none();
-        let z1 = z.checked_sub(2).is_none();
+    // Every cell on the edge is an `OuterWall`, every other cell with an even
+    // co-ordinate is a `Wall` and the rest are rooms waiting to be visited
+    for ((x, y, z, w), cell) in maze.0.indexed_iter_mut() {
+        let coords = [x, y, z, w];
 
-        if visited.is_empty() {
-            break;
-        } else if (x1 || mz.get((x - 2, y, z)) != Some(&MazeCell::Unvisited))
-            && mz.get((x + 2, y, z)) != Some(&MazeCell::Unvisited)
-            && (y1 || mz.get((x, y - 2, z)) != Some(&MazeCell::Unvisited))
-            && mz.get((x, y + 2, z)) != Some(&MazeCell::Unvisited)
-            && (z1 || mz.get((x, y, z - 2)) != Some(&MazeCell::Unvisited))
-            && mz.get((x, y, z + 2)) != Some(&MazeCell::Unvisited)
+        *cell = if coords
+            .iter()
+            .zip(shape.iter())
+            .any(|(&coord, &size)| coord == 0 || coord == size - 1)
         {
-            pos = visited.pop().unwrap();
+            MazeCell::OuterWall
+        } else if coords.iter().any(|coord| coord % 2 == 0) {
+            MazeCell::Wall
         } else {
-            let mut axis: AxisDir;
+            MazeCell::Unvisited
+        };
+    }
 
-            loop {
-                pos = match rng.sample(range) {
-                    0 => {
-                        axis = AxisDir::XPos;
-                        (x + 2, y, z)
-                    }
-                    1 => {
-                        axis = AxisDir::YPos;
-                        (x, y + 2, z)
-                    }
-                    2 => {
-                        axis = AxisDir::ZPos;
-                        (x, y, z + 2)
-                    }
-                    3 if !x1 => {
-                        axis = AxisDir::XNeg;
-                        (x - 2, y, z)
-                    }
-                    4 if !y1 => {
-                        axis = AxisDir::YNeg;
-                        (x, y - 2, z)
-                    }
-                    5 if !z1 => {
-                        axis = AxisDir::ZNeg;
-                        (x, y, z - 2)
+    let start = options.start.pick(&maze, rng);
+    let last = generator.carve(&mut maze, start, rng);
+    if options.braid > 0 {
+        braid(&mut maze, options.braid, rng);
+    }
+    let (start, end) = options.exit.place(&maze, start, last, rng);
+
+    maze.0[start] = MazeCell::Start;
+    maze.0[end] = MazeCell::End;
+
+    maze
+}
+
+/// An algorithm that carves the passages of a maze. Each one gives mazes with
+/// a different texture
+pub trait MazeGenerator {
+    /// Carves passages through `maze`, where every room (a cell with only odd
+    /// co-ordinates) starts as `MazeCell::Unvisited`, beginning at `start`.
+    /// Every room and every knocked through wall is left as `MazeCell::Cell`.
+    ///
+    /// Returns the last room that was carved
+    fn carve(&self, maze: &mut Maze, start: MazePos, rng: &mut dyn RngCore) -> MazePos;
+}
+
+/// The original generator. It walks to random unvisited rooms and backtracks
+/// when it gets stuck, which gives long winding corridors
+pub struct RecursiveBacktracker;
+
+impl MazeGenerator for RecursiveBacktracker {
+    fn carve(&self, maze: &mut Maze, start: MazePos, rng: &mut dyn RngCore) -> MazePos {
+        let mut mz = maze.0.view_mut();
+
+        let mut visited: Vec<MazePos> = Vec::with_capacity(mz.len() / 2);
+        let mut tot_visited: Vec<MazePos> = Vec::with_capacity(mz.len() / 2);
+        let mut pos = start;
+
+        *mz.get_mut(pos).unwrap() = MazeCell::Cell;
+        visited.push(pos);
+        tot_visited.push(pos);
+
+        // `u32` is sampled instead of `usize` so 32 and 64-bit platforms agree
+        let range = Uniform::new(0, DIRECTIONS.len() as u32);
+
+        loop {
+            if visited.is_empty() {
+                break;
+            } else if DIRECTIONS.iter().all(|&(axis, positive)| {
+                step(pos, axis, positive, 2).and_then(|next| mz.get(next))
+                    != Some(&MazeCell::Unvisited)
+            }) {
+                pos = visited.pop().unwrap();
+            } else {
+                let (axis, positive) = loop {
+                    let (axis, positive) = DIRECTIONS[rng.sample(range) as usize];
+
+                    let cell_inner = step(pos, axis, positive, 2).and_then(|next| mz.get(next));
+                    if cell_inner == Some(&MazeCell::Unvisited) {
+                        break (axis, positive);
                     }
-                    _ => continue,
                 };
 
-                let cell_inner = mz.get(pos);
-                if cell_inner == Some(&MazeCell::Unvisited) {
-                    break;
-                }
+                // Both are in bounds as the room 2 steps away was found above
+                let wall = step(pos, axis, positive, 1).unwrap();
+                pos = step(pos, axis, positive, 2).unwrap();
+
+                *mz.get_mut(pos).unwrap() = MazeCell::Cell;
+                *mz.get_mut(wall).unwrap() = MazeCell::Cell;
+
+                visited.push(pos);
+                tot_visited.push(pos);
             }
+        }
+
+        tot_visited.pop().unwrap()
+    }
+}
+
+/// Moves `pos` `dist` cells along `axis`, returning `None` if that would
+/// underflow
+pub(crate) fn step(pos: MazePos, axis: usize, positive: bool, dist: usize) -> Option<MazePos> {
+    let mut coords = [pos.0, pos.1, pos.2, pos.3];
+    coords[axis] = if positive {
+        coords[axis] + dist
+    } else {
+        coords[axis].checked_sub(dist)?
+    };
 
-            *mz.get_mut(pos).unwrap() = MazeCell::Cell;
-            *mz.get_mut(match axis {
-                AxisDir::XPos => (x + 1, y, z),
-                AxisDir::YPos => (x, y + 1, z),
-                AxisDir::ZPos => (x, y, z + 1),
-                AxisDir::XNeg => (x - 1, y, z),
-                AxisDir::YNeg => (x, y - 1, z),
-                AxisDir::ZNeg => (x, y, z - 1),
-            })
-            .unwrap() = MazeCell::Cell;
-
-            visited.push(pos);
-            tot_visited.push(pos);
+    Some((coords[0], coords[1], coords[2], coords[3]))
+}
+
+/// Removes `percent` of the dead ends of a carved maze by knocking through one
+/// of their walls, which makes loops so there is more than one route. A dead
+/// end is joined to another dead end when it can be, so one wall can remove
+/// 2 of them
+///
+/// **Panics** if `percent` is greater than 100
+pub fn braid(maze: &mut Maze, percent: u8, rng: &mut dyn RngCore) {
+    assert!(
+        percent <= 100,
+        "Only up to 100% of dead ends can be removed"
+    );
+
+    let is_dead_end = |maze: &Maze, room: MazePos| {
+        maze.0[room].is_traversable()
+            && neighbours(maze, room)
+                .iter()
+                .filter(|&&(_, wall)| maze.0[wall].is_traversable())
+                .count()
+                == 1
+    };
+
+    let mut dead_ends = rooms(maze)
+        .into_iter()
+        .filter(|&room| is_dead_end(maze, room))
+        .collect::<Vec<_>>();
+    shuffle(rng, &mut dead_ends);
+    let to_remove = dead_ends.len() * usize::from(percent) / 100;
+
+    for &room in dead_ends.iter().take(to_remove) {
+        // An earlier wall may have already joined this dead end up
+        if !is_dead_end(maze, room) {
+            continue;
+        }
+
+        let closed = neighbours(maze, room)
+            .into_iter()
+            .filter(|&(_, wall)| maze.0[wall] == MazeCell::Wall)
+            .collect::<Vec<_>>();
+        let best = closed
+            .iter()
+            .filter(|&&(next, _)| is_dead_end(maze, next))
+            .cloned()
+            .collect::<Vec<_>>();
+        let choices = if best.is_empty() { closed } else { best };
+
+        // A maze with only one room has nothing to join to
+        if !choices.is_empty() {
+            let (_, wall) = choices[pick(rng, choices.len())];
+            maze.0[wall] = MazeCell::Cell;
         }
     }
+}
 
-    *mz.get_mut(tot_visited.pop().unwrap()).unwrap() = MazeCell::End;
+/// The rooms next to `room` paired with the wall between them, in the order of
+/// `DIRECTIONS`
+pub(crate) fn neighbours(maze: &Maze, room: MazePos) -> Vec<(MazePos, MazePos)> {
+    DIRECTIONS
+        .iter()
+        .filter_map(|&(axis, positive)| {
+            let next = step(room, axis, positive, 2)?;
+            match maze.0.get(next) {
+                Some(MazeCell::OuterWall) | Some(MazeCell::Wall) | None => None,
+                Some(_) => Some((next, step(room, axis, positive, 1)?)),
+            }
+        })
+        .collect()
+}
 
-    maze
+/// Every room in `maze` in index order
+pub(crate) fn rooms(maze: &Maze) -> Vec<MazePos> {
+    maze.0
+        .indexed_iter()
+        .filter(|((x, y, z, w), cell)| {
+            x % 2 == 1 && y % 2 == 1 && z % 2 == 1 && w % 2 == 1 && **cell != MazeCell::OuterWall
+        })
+        .map(|(pos, _)| pos)
+        .collect()
+}
+
+/// Knocks through `wall` and marks `room` as carved
+pub(crate) fn carve_passage(maze: &mut Maze, wall: MazePos, room: MazePos) {
+    maze.0[wall] = MazeCell::Cell;
+    maze.0[room] = MazeCell::Cell;
+}
+
+/// Picks a number below `len`. `u32` is sampled instead of `usize` so 32 and
+/// 64-bit platforms agree
+#[allow(clippy::cast_possible_truncation)]
+pub(crate) fn pick(rng: &mut dyn RngCore, len: usize) -> usize {
+    rng.gen_range(0, len as u32) as usize
+}
+
+/// Shuffles `items` using `pick()` so the order is the same on every platform
+pub(crate) fn shuffle<T>(rng: &mut dyn RngCore, items: &mut [T]) {
+    for pos in (1..items.len()).rev() {
+        items.swap(pos, pick(rng, pos + 1));
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::MazeMode;
 
     #[test]
     fn no_4s() {
-        let mz = gen_maze();
-        for cell in mz.0.iter() {
-            if cell == &MazeCell::Unvisited {
-                panic!("Unvisited cell found");
+        for &shape in &[
+            MazeMode::ThreeD.default_shape(),
+            MazeMode::FourD.default_shape(),
+            MazeMode::ThreeD.shape((21, 21, 5, 0)),
+            (5, 9, 3, 7),
+        ] {
+            let mz = gen_maze(shape);
+            assert_eq!(mz.shape(), shape);
+            for cell in mz.0.iter() {
+                if cell == &MazeCell::Unvisited {
+                    panic!("Unvisited cell found");
+                }
             }
         }
     }
+
+    #[test]
+    fn four_d_carves_along_w() {
+        let mz = gen_maze(MazeMode::FourD.default_shape());
+        let carved_w =
+            mz.0.indexed_iter()
+                .any(|((_, _, _, w), cell)| w % 2 == 0 && cell == &MazeCell::Cell);
+
+        assert!(carved_w, "No passage was carved along the W axis");
+    }
+
+    #[test]
+    fn three_d_stays_in_one_layer() {
+        let mz = gen_maze(MazeMode::ThreeD.default_shape());
+        for ((_, _, _, w), cell) in mz.0.indexed_iter() {
+            if w != 1 {
+                assert_eq!(cell, &MazeCell::OuterWall);
+            }
+        }
+    }
+
+    #[test]
+    fn same_seed_same_maze() {
+        for &shape in &[MazeMode::ThreeD.default_shape(), (9, 5, 7, 5)] {
+            assert_eq!(
+                gen_maze_with_seed(shape, 1234).0,
+                gen_maze_with_seed(shape, 1234).0
+            );
+        }
+
+        assert_ne!(
+            gen_maze_with_seed((9, 9, 9, 9), 1).0,
+            gen_maze_with_seed((9, 9, 9, 9), 2).0
+        );
+    }
+
+    #[test]
+    fn seed_golden() {
+        // If this changes then every shared seed makes a different maze
+        let mz = gen_maze_with_seed((9, 9, 9, 3), 42);
+        let cells: Vec<usize> =
+            mz.0.iter()
+                .enumerate()
+                .filter(|(_, cell)| *cell == &MazeCell::Cell)
+                .map(|(index, _)| index)
+                .collect();
+
+        assert_eq!(mz.end_pos(), (5, 1, 1, 1));
+        assert_eq!(cells.len(), 125);
+        assert_eq!(cells.iter().sum::<usize>(), 138_083);
+    }
+
+    #[should_panic]
+    #[test]
+    fn too_small() {
+        gen_maze((7, 7, 2, 7));
+    }
+
+    #[test]
+    fn braid_removes_dead_ends() {
+        use crate::maze_lib::maze_analysis::analyse;
+        use crate::maze_lib::maze_solve::{solve, AStar, MazeSolver};
+
+        let braided = |braid| {
+            let options = MazeOptions {
+                braid,
+                ..MazeOptions::default()
+            };
+            gen_maze_with_options(
+                &RecursiveBacktracker,
+                (11, 11, 7, 5),
+                &options,
+                &mut seeded_rng(8),
+            )
+        };
+        let perfect = analyse(&braided(0));
+        let half = analyse(&braided(50));
+        let full = analyse(&braided(100));
+
+        assert_eq!(perfect.loops, 0);
+        assert!(half.dead_ends < perfect.dead_ends);
+        assert!(half.loops > 0);
+        assert_eq!(full.dead_ends, 0);
+        assert!(full.loops > half.loops);
+
+        // There is more than one route now but the shortest is still found
+        let maze = braided(100);
+        let shortest = solve(&maze).unwrap();
+        assert_eq!(AStar.solve_maze(&maze).unwrap().len(), shortest.len());
+        assert!(shortest.len() - 1 <= perfect.solution_length.unwrap());
+    }
 }
 
 pub mod week_two {
@@ -0,0 +1,193 @@
+//! How the player moves through a maze. Moves are given as they look on the
+//! screen (left, right, up and down on the slice, or along the depth axis)
+//! and `try_move` turns them into a step along one of the 4 axes. Nothing is
+//! changed by `try_move` so it can be used by any frontend and by tests
+use crate::{Maze, MazeAxis4, MazePos};
+use std::{error::Error, fmt};
+
+/// A step along one axis of the maze
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Direction {
+    /// The index of the axis, 0 to 3 for X to W
+    pub axis: usize,
+    /// Whether the step goes up the axis
+    pub positive: bool,
+}
+
+/// A move as the player sees it on a slice
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Move {
+    Left,
+    Right,
+    Up,
+    Down,
+    /// Up the depth axis of the plane (`MazeAxis4::depth_axis`)
+    DepthUp,
+    /// Down the depth axis of the plane
+    DepthDown,
+}
+
+/// Why a move couldn't be made
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum MoveError {
+    /// The move would leave the maze
+    OutOfBounds,
+    /// There is a wall at this position
+    Blocked(MazePos),
+}
+
+impl Direction {
+    /// Every direction, in pairs going up and down each axis
+    pub const ALL: [Direction; 8] = [
+        Direction::new(0, true),
+        Direction::new(0, false),
+        Direction::new(1, true),
+        Direction::new(1, false),
+        Direction::new(2, true),
+        Direction::new(2, false),
+        Direction::new(3, true),
+        Direction::new(3, false),
+    ];
+
+    pub const fn new(axis: usize, positive: bool) -> Self {
+        Self { axis, positive }
+    }
+
+    /// The direction going the other way
+    pub fn reverse(self) -> Self {
+        Self::new(self.axis, !self.positive)
+    }
+}
+
+impl Move {
+    /// Every move
+    pub const ALL: [Move; 6] = [
+        Move::Left,
+        Move::Right,
+        Move::Up,
+        Move::Down,
+        Move::DepthUp,
+        Move::DepthDown,
+    ];
+
+    /// The direction this move goes in when the maze is cut through `plane`.
+    /// Left and right go along the first axis of the plane, and up and down
+    /// along the second with up going towards 0 like the screen
+    pub fn direction(self, plane: MazeAxis4) -> Direction {
+        let (first, second) = plane.visible_axes();
+        match self {
+            Move::Left => Direction::new(first, false),
+            Move::Right => Direction::new(first, true),
+            Move::Up => Direction::new(second, false),
+            Move::Down => Direction::new(second, true),
+            Move::DepthUp => Direction::new(plane.depth_axis(), true),
+            Move::DepthDown => Direction::new(plane.depth_axis(), false),
+        }
+    }
+}
+
+/// Works out where the player at `pos` looking at `plane` ends up after
+/// `player_move`. Returns the new position, or why the player can't move
+pub fn try_move(
+    maze: &Maze,
+    pos: MazePos,
+    plane: MazeAxis4,
+    player_move: Move,
+) -> Result<MazePos, MoveError> {
+    let next = maze
+        .neighbour(pos, player_move.direction(plane))
+        .ok_or(MoveError::OutOfBounds)?;
+
+    if maze.0[next].is_traversable() {
+        Ok(next)
+    } else {
+        Err(MoveError::Blocked(next))
+    }
+}
+
+impl fmt::Display for MoveError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            MoveError::OutOfBounds => write!(f, "That would leave the maze"),
+            MoveError::Blocked(pos) => write!(f, "There is a wall at {:?}", pos),
+        }
+    }
+}
+
+impl Error for MoveError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MazeCell;
+
+    /// A 5x3 room with no outer wall, so moves can run off the edge
+    fn room() -> Maze {
+        let mut maze = Maze::new_empty((5, 3, 3, 1));
+        for cell in maze.0.iter_mut() {
+            *cell = MazeCell::Cell;
+        }
+        maze.0[(2, 1, 1, 0)] = MazeCell::Wall;
+        maze
+    }
+
+    #[test]
+    fn moves_on_the_plane() {
+        let maze = room();
+        let pos = (1, 1, 1, 0);
+        assert_eq!(
+            try_move(&maze, pos, MazeAxis4::XY, Move::Left),
+            Ok((0, 1, 1, 0))
+        );
+        assert_eq!(
+            try_move(&maze, pos, MazeAxis4::XY, Move::Up),
+            Ok((1, 0, 1, 0))
+        );
+        assert_eq!(
+            try_move(&maze, pos, MazeAxis4::YZ, Move::Right),
+            Ok((1, 2, 1, 0))
+        );
+        assert_eq!(
+            try_move(&maze, pos, MazeAxis4::XY, Move::DepthUp),
+            Ok((1, 1, 2, 0))
+        );
+        assert_eq!(
+            try_move(&maze, pos, MazeAxis4::XY, Move::Right),
+            Err(MoveError::Blocked((2, 1, 1, 0)))
+        );
+    }
+
+    #[test]
+    fn edges_are_out_of_bounds() {
+        let maze = room();
+        let corner = (0, 0, 0, 0);
+        for &player_move in &[Move::Left, Move::Up, Move::DepthDown] {
+            assert_eq!(
+                try_move(&maze, corner, MazeAxis4::XY, player_move),
+                Err(MoveError::OutOfBounds)
+            );
+        }
+        // W is only 1 cell long
+        assert_eq!(
+            try_move(&maze, corner, MazeAxis4::XW, Move::Down),
+            Err(MoveError::OutOfBounds)
+        );
+        assert_eq!(
+            try_move(&maze, (4, 2, 2, 0), MazeAxis4::XZ, Move::Down),
+            Err(MoveError::OutOfBounds)
+        );
+    }
+
+    #[test]
+    fn directions() {
+        for dir in &Direction::ALL {
+            assert_eq!(dir.reverse().reverse(), *dir);
+            assert_ne!(dir.reverse(), *dir);
+        }
+        assert_eq!(Move::Down.direction(MazeAxis4::ZW), Direction::new(3, true));
+        assert_eq!(
+            Move::DepthDown.direction(MazeAxis4::YW),
+            Direction::new(0, false)
+        );
+    }
+}
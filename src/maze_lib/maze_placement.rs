@@ -0,0 +1,323 @@
+//! Where the start and the exit of a maze go. The exit used to always be the
+//! last room the generator carved, which can be right next to the start, so
+//! it can be placed by how far it is from the start instead
+use crate::maze_lib::maze_gen::{pick, rooms};
+use crate::maze_lib::maze_solve::distances;
+use crate::{Maze, MazeCell, MazePos};
+use rand::RngCore;
+use std::{fmt, str::FromStr};
+
+/// Where the start of a maze goes
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum StartPlacement {
+    /// The first room, `(1, 1, 1, 1)`
+    Corner,
+    /// The room at this position
+    At(MazePos),
+    /// A random room
+    Random,
+}
+
+/// Where the exit of a maze goes
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ExitPlacement {
+    /// The last room the generator carved
+    LastCarved,
+    /// The room farthest from the start
+    Farthest,
+    /// The 2 rooms farthest from each other. This moves the start as well.
+    /// It is only exact on a perfect maze, a braided maze (see
+    /// `MazeOptions::braid`) gets 2 rooms that are far apart but may not be
+    /// the farthest
+    Diameter,
+    /// A random room at least `min_length` steps from the start. If no room is
+    /// that far away the farthest room is used, which `check` reports
+    Random { min_length: usize },
+}
+
+impl StartPlacement {
+    /// Picks the start of `maze`, which has had its walls laid but has not
+    /// been carved yet
+    ///
+    /// **Panics** if a position is given that isn't a room of the maze
+    pub fn pick(self, maze: &Maze, rng: &mut dyn RngCore) -> MazePos {
+        match self {
+            StartPlacement::Corner => (1, 1, 1, 1),
+            StartPlacement::At(pos) => {
+                assert!(
+                    maze.0.get(pos) == Some(&MazeCell::Unvisited),
+                    "The start needs to be a room inside the maze"
+                );
+                pos
+            }
+            StartPlacement::Random => {
+                let rooms = rooms(maze);
+                rooms[pick(rng, rooms.len())]
+            }
+        }
+    }
+}
+
+impl ExitPlacement {
+    /// Places the exit of `maze` once it has been carved from `start`. `last`
+    /// is the last room the generator carved.
+    ///
+    /// Returns the start and the exit
+    pub fn place(
+        self,
+        maze: &Maze,
+        start: MazePos,
+        last: MazePos,
+        rng: &mut dyn RngCore,
+    ) -> (MazePos, MazePos) {
+        match self {
+            ExitPlacement::LastCarved => (start, last),
+            ExitPlacement::Farthest => (start, farthest_room(maze, start).0),
+            ExitPlacement::Diameter => {
+                // The room farthest from any room is one end of the longest
+                // route in a perfect maze
+                let (first, _) = farthest_room(maze, start);
+                (first, farthest_room(maze, first).0)
+            }
+            ExitPlacement::Random { min_length } => {
+                let candidates = room_distances(maze, start)
+                    .into_iter()
+                    .filter(|&(_, distance)| distance >= min_length.max(1))
+                    .map(|(room, _)| room)
+                    .collect::<Vec<_>>();
+
+                if candidates.is_empty() {
+                    (start, farthest_room(maze, start).0)
+                } else {
+                    (start, candidates[pick(rng, candidates.len())])
+                }
+            }
+        }
+    }
+}
+
+impl ExitPlacement {
+    /// Checks that the exit of `maze`, placed by `place`, is as far from the
+    /// start as this placement asks for. Only `Random` can miss, when the
+    /// maze has no room far enough away
+    ///
+    /// Returns what went wrong if it missed
+    pub fn check(self, maze: &Maze) -> Result<(), String> {
+        match self {
+            ExitPlacement::Random { min_length } => {
+                let length = distances(maze, maze.start_pos())[maze.end_pos()].unwrap_or(0);
+                if length >= min_length.max(1) {
+                    Ok(())
+                } else {
+                    Err(format!(
+                        "No room is {} steps from the start, the exit is {} away",
+                        min_length, length
+                    ))
+                }
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Every room that can be reached from `from` with the number of steps to it
+fn room_distances(maze: &Maze, from: MazePos) -> Vec<(MazePos, usize)> {
+    let distances = distances(maze, from);
+    rooms(maze)
+        .into_iter()
+        .filter_map(|room| distances[room].map(|distance| (room, distance)))
+        .collect()
+}
+
+/// The room farthest from `from` and the number of steps to it
+fn farthest_room(maze: &Maze, from: MazePos) -> (MazePos, usize) {
+    room_distances(maze, from)
+        .into_iter()
+        .max_by_key(|&(_, distance)| distance)
+        .unwrap_or((from, 0))
+}
+
+impl Default for StartPlacement {
+    fn default() -> Self {
+        StartPlacement::Corner
+    }
+}
+
+impl Default for ExitPlacement {
+    fn default() -> Self {
+        ExitPlacement::LastCarved
+    }
+}
+
+impl fmt::Display for StartPlacement {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            StartPlacement::Corner => write!(f, "corner"),
+            StartPlacement::At((x, y, z, w)) => write!(f, "{},{},{},{}", x, y, z, w),
+            StartPlacement::Random => write!(f, "random"),
+        }
+    }
+}
+
+impl FromStr for StartPlacement {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name {
+            "corner" => Ok(StartPlacement::Corner),
+            "random" => Ok(StartPlacement::Random),
+            _ => {
+                let coords = name
+                    .split(',')
+                    .map(|coord| coord.parse::<usize>())
+                    .collect::<Result<Vec<_>, _>>()
+                    .map_err(|_| format!("{:?} is not a start position", name))?;
+
+                match coords.as_slice() {
+                    [x, y, z, w] if coords.iter().all(|coord| coord % 2 == 1) => {
+                        Ok(StartPlacement::At((*x, *y, *z, *w)))
+                    }
+                    [_, _, _, _] => {
+                        Err("The start needs to be a room (every co-ordinate odd)".to_string())
+                    }
+                    _ => Err(format!("{:?} needs 4 co-ordinates such as 1,1,1,1", name)),
+                }
+            }
+        }
+    }
+}
+
+impl fmt::Display for ExitPlacement {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ExitPlacement::LastCarved => write!(f, "last-carved"),
+            ExitPlacement::Farthest => write!(f, "farthest"),
+            ExitPlacement::Diameter => write!(f, "diameter"),
+            ExitPlacement::Random { min_length } => write!(f, "random-{}", min_length),
+        }
+    }
+}
+
+impl FromStr for ExitPlacement {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name {
+            "last-carved" => Ok(ExitPlacement::LastCarved),
+            "farthest" => Ok(ExitPlacement::Farthest),
+            "diameter" => Ok(ExitPlacement::Diameter),
+            _ => Some(name)
+                .filter(|name| name.starts_with("random-"))
+                .and_then(|name| name["random-".len()..].parse().ok())
+                .map(|min_length| ExitPlacement::Random { min_length })
+                .ok_or_else(|| format!("{:?} is not an exit placement", name)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::maze_lib::maze_gen::{gen_maze_with_options, seeded_rng, MazeOptions};
+    use crate::maze_lib::maze_solve::solve;
+    use crate::Algorithm;
+
+    fn gen(start: StartPlacement, exit: ExitPlacement, seed: u64) -> Maze {
+        let options = MazeOptions {
+            start,
+            exit,
+            braid: 0,
+        };
+        gen_maze_with_options(
+            &Algorithm::Prim,
+            (11, 9, 7, 5),
+            &options,
+            &mut seeded_rng(seed),
+        )
+    }
+
+    fn solution_length(maze: &Maze) -> usize {
+        solve(maze).unwrap().len() - 1
+    }
+
+    #[test]
+    fn farthest_is_farthest() {
+        for seed in 0..5 {
+            let maze = gen(StartPlacement::Corner, ExitPlacement::Farthest, seed);
+            let start = maze.start_pos();
+            let longest = room_distances(&maze, start)
+                .into_iter()
+                .map(|(_, distance)| distance)
+                .max();
+
+            assert_eq!(start, (1, 1, 1, 1));
+            assert_eq!(Some(solution_length(&maze)), longest);
+        }
+    }
+
+    #[test]
+    fn diameter_is_at_least_as_long() {
+        for seed in 0..5 {
+            let farthest = gen(StartPlacement::Random, ExitPlacement::Farthest, seed);
+            let diameter = gen(StartPlacement::Random, ExitPlacement::Diameter, seed);
+            assert!(solution_length(&diameter) >= solution_length(&farthest));
+        }
+    }
+
+    #[test]
+    fn random_exit_keeps_its_distance() {
+        for seed in 0..5 {
+            let maze = gen(
+                StartPlacement::At((5, 3, 3, 1)),
+                ExitPlacement::Random { min_length: 20 },
+                seed,
+            );
+            assert_eq!(maze.start_pos(), (5, 3, 3, 1));
+            assert!(solution_length(&maze) >= 20);
+            assert!(ExitPlacement::Random { min_length: 20 }
+                .check(&maze)
+                .is_ok());
+        }
+
+        // Too far for any room so the farthest room is used, and `check` says
+        // so
+        let exit = ExitPlacement::Random { min_length: 10_000 };
+        let maze = gen(StartPlacement::Corner, exit, 0);
+        let farthest = gen(StartPlacement::Corner, ExitPlacement::Farthest, 0);
+        assert_eq!(maze.end_pos(), farthest.end_pos());
+        assert!(exit.check(&maze).is_err());
+    }
+
+    #[test]
+    #[should_panic]
+    fn start_in_a_wall() {
+        gen(StartPlacement::At((2, 1, 1, 1)), ExitPlacement::Farthest, 0);
+    }
+
+    #[test]
+    fn names_round_trip() {
+        let starts = [
+            StartPlacement::Corner,
+            StartPlacement::Random,
+            StartPlacement::At((3, 5, 1, 1)),
+        ];
+        for start in &starts {
+            assert_eq!(start.to_string().parse::<StartPlacement>(), Ok(*start));
+        }
+
+        let exits = [
+            ExitPlacement::LastCarved,
+            ExitPlacement::Farthest,
+            ExitPlacement::Diameter,
+            ExitPlacement::Random { min_length: 12 },
+        ];
+        for exit in &exits {
+            assert_eq!(exit.to_string().parse::<ExitPlacement>(), Ok(*exit));
+        }
+
+        assert!("random-".parse::<ExitPlacement>().is_err());
+        assert!("random-random-5".parse::<ExitPlacement>().is_err());
+        assert!("2,1,1,1".parse::<StartPlacement>().is_err());
+        assert!("1,1,1".parse::<StartPlacement>().is_err());
+    }
+}
@@ -0,0 +1,489 @@
+//! Solvers that find a route through a `Maze`. Each one returns the route as
+//! every position walked through, from the first position to the last, so the
+//! player could follow it one cell at a time
+use crate::maze_lib::maze_gen::{step, DIRECTIONS};
+use crate::{try_move, Maze, MazeAxis4, MazeMode, MazePos, Move};
+use ndarray::Array4;
+use std::{
+    cmp::Reverse,
+    collections::{hash_map::Entry, BinaryHeap, HashMap, HashSet, VecDeque},
+};
+
+/// Something that can find a route between 2 positions of a maze
+pub trait MazeSolver {
+    /// Finds a route from `from` to `to`. Both ends are included in the route.
+    /// Returns `None` if the solver can't reach `to`
+    fn solve(&self, maze: &Maze, from: MazePos, to: MazePos) -> Option<Vec<MazePos>>;
+
+    /// Finds a route from `MazeCell::Start` to `MazeCell::End`
+    ///
+    /// **Panics** if the maze has no Start or End
+    fn solve_maze(&self, maze: &Maze) -> Option<Vec<MazePos>> {
+        self.solve(maze, maze.start_pos(), maze.end_pos())
+    }
+}
+
+/// Breadth-first search. It always finds the shortest route
+pub struct BreadthFirst;
+
+/// A* search using the Manhattan distance. It finds the shortest route like
+/// `BreadthFirst` but looks at fewer cells to do so
+pub struct AStar;
+
+/// Follows the wall with one hand. The maze has no left or right in 4D, so it
+/// turns through the 8 directions in a fixed order from the one it came in by.
+/// The route includes every dead end it walks into, so it is a lot longer than
+/// the shortest route. It gives up if it walks in a loop without reaching the
+/// end
+pub struct WallFollower;
+
+/// Finds the shortest route from `from` to `to`
+pub fn shortest_path(maze: &Maze, from: MazePos, to: MazePos) -> Option<Vec<MazePos>> {
+    BreadthFirst.solve(maze, from, to)
+}
+
+/// Finds the shortest route from `MazeCell::Start` to `MazeCell::End`
+///
+/// **Panics** if the maze has no Start or End
+pub fn solve(maze: &Maze) -> Option<Vec<MazePos>> {
+    BreadthFirst.solve_maze(maze)
+}
+
+/// The number of steps from `from` to every position, or `None` where a
+/// position can't be reached
+pub fn distances(maze: &Maze, from: MazePos) -> Array4<Option<usize>> {
+    let mut distances = Array4::from_elem(maze.shape(), None);
+    if !is_open(maze, from) {
+        return distances;
+    }
+
+    let mut queue = VecDeque::new();
+    distances[from] = Some(0);
+    queue.push_back((from, 0));
+
+    while let Some((pos, distance)) = queue.pop_front() {
+        for next in open_neighbours(maze, pos) {
+            if distances[next].is_none() {
+                distances[next] = Some(distance + 1);
+                queue.push_back((next, distance + 1));
+            }
+        }
+    }
+    distances
+}
+
+/// Finds the quickest way for the player to get from `from` to `to`, starting
+/// on `plane`. The player can only step along the 2 axes of the plane they
+/// are looking at and its depth axis (`MazeAxis4::depth_axis`), so turning
+/// onto the next or previous plane (`MazeAxis4::cycle` and `cycle_back`)
+/// counts as a move as well as each step.
+///
+/// Returns every position and plane the player is in along the way, with
+/// both ends included, or `None` if `to` can't be reached
+pub fn solve_with_rotations(
+    maze: &Maze,
+    from: MazePos,
+    to: MazePos,
+    plane: MazeAxis4,
+    mode: MazeMode,
+) -> Option<Vec<(MazePos, MazeAxis4)>> {
+    if !is_open(maze, from) || !is_open(maze, to) {
+        return None;
+    }
+
+    let mut parents = HashMap::new();
+    let mut queue = VecDeque::new();
+    parents.insert((from, plane), (from, plane));
+    queue.push_back((from, plane));
+
+    while let Some((pos, plane)) = queue.pop_front() {
+        if pos == to {
+            // Only the first state is its own parent
+            let mut state = (pos, plane);
+            let mut route = vec![state];
+            while parents[&state] != state {
+                state = parents[&state];
+                route.push(state);
+            }
+            route.reverse();
+            return Some(route);
+        }
+
+        let (mut forward, mut back) = (plane, plane);
+        forward.cycle(mode);
+        back.cycle_back(mode);
+        let steps = Move::ALL
+            .iter()
+            .filter_map(|&player_move| try_move(maze, pos, plane, player_move).ok())
+            .map(|next| (next, plane));
+        let turns = vec![(pos, forward), (pos, back)];
+
+        for state in steps.chain(turns).collect::<Vec<_>>() {
+            if let Entry::Vacant(entry) = parents.entry(state) {
+                entry.insert((pos, plane));
+                queue.push_back(state);
+            }
+        }
+    }
+    None
+}
+
+/// Finds the quickest way from `from` to `to` without leaving the slice
+/// through `from` cut along `plane`, so only left, right, up and down are
+/// used.
+///
+/// Returns the moves to make, or `None` if `to` isn't on the slice or can't
+/// be reached without leaving it
+pub fn slice_path(maze: &Maze, from: MazePos, to: MazePos, plane: MazeAxis4) -> Option<Vec<Move>> {
+    if !is_open(maze, from) || !is_open(maze, to) {
+        return None;
+    }
+
+    let mut parents = HashMap::new();
+    let mut queue = VecDeque::new();
+    parents.insert(from, None);
+    queue.push_back(from);
+
+    while let Some(pos) = queue.pop_front() {
+        if pos == to {
+            let mut pos = pos;
+            let mut moves = Vec::new();
+            while let Some((previous, player_move)) = parents[&pos] {
+                moves.push(player_move);
+                pos = previous;
+            }
+            moves.reverse();
+            return Some(moves);
+        }
+
+        for &player_move in &[Move::Left, Move::Right, Move::Up, Move::Down] {
+            if let Ok(next) = try_move(maze, pos, plane, player_move) {
+                if let Entry::Vacant(entry) = parents.entry(next) {
+                    entry.insert(Some((pos, player_move)));
+                    queue.push_back(next);
+                }
+            }
+        }
+    }
+    None
+}
+
+/// The fewest steps and turns that get the player from `MazeCell::Start` to
+/// `MazeCell::End`, starting on the XY plane. Scores are measured against it
+///
+/// **Panics** if the maze has no Start or End
+pub fn par(maze: &Maze, mode: MazeMode) -> Option<usize> {
+    solve_with_rotations(maze, maze.start_pos(), maze.end_pos(), MazeAxis4::XY, mode)
+        .map(|route| route.len() - 1)
+}
+
+/// Every traversable position one step away from `pos`
+pub(crate) fn open_neighbours(maze: &Maze, pos: MazePos) -> impl Iterator<Item = MazePos> + '_ {
+    DIRECTIONS.iter().filter_map(move |&(axis, positive)| {
+        step(pos, axis, positive, 1).filter(|&next| is_open(maze, next))
+    })
+}
+
+/// Whether `pos` is inside the maze and can be walked on
+fn is_open(maze: &Maze, pos: MazePos) -> bool {
+    maze.0.get(pos).map_or(false, |cell| cell.is_traversable())
+}
+
+/// Walks back through `parents` from `to` to build the route
+fn trace_route(parents: &Array4<Option<MazePos>>, from: MazePos, to: MazePos) -> Vec<MazePos> {
+    let mut route = vec![to];
+    let mut pos = to;
+    while pos != from {
+        /* Every position that was reached has a parent */
+        pos = parents[pos].unwrap();
+        route.push(pos);
+    }
+    route.reverse();
+    route
+}
+
+/// The number of steps between 2 positions if there were no walls
+fn manhattan(first: MazePos, second: MazePos) -> usize {
+    let distance = |a: usize, b: usize| if a > b { a - b } else { b - a };
+    distance(first.0, second.0)
+        + distance(first.1, second.1)
+        + distance(first.2, second.2)
+        + distance(first.3, second.3)
+}
+
+impl MazeSolver for BreadthFirst {
+    fn solve(&self, maze: &Maze, from: MazePos, to: MazePos) -> Option<Vec<MazePos>> {
+        if !is_open(maze, from) || !is_open(maze, to) {
+            return None;
+        }
+
+        let mut parents = Array4::from_elem(maze.shape(), None);
+        let mut queue = VecDeque::new();
+        parents[from] = Some(from);
+        queue.push_back(from);
+
+        while let Some(pos) = queue.pop_front() {
+            if pos == to {
+                return Some(trace_route(&parents, from, to));
+            }
+            for next in open_neighbours(maze, pos) {
+                if parents[next].is_none() {
+                    parents[next] = Some(pos);
+                    queue.push_back(next);
+                }
+            }
+        }
+        None
+    }
+}
+
+impl MazeSolver for AStar {
+    fn solve(&self, maze: &Maze, from: MazePos, to: MazePos) -> Option<Vec<MazePos>> {
+        if !is_open(maze, from) || !is_open(maze, to) {
+            return None;
+        }
+
+        let mut parents = Array4::from_elem(maze.shape(), None);
+        let mut distances = Array4::from_elem(maze.shape(), usize::max_value());
+        let mut open = BinaryHeap::new();
+        parents[from] = Some(from);
+        distances[from] = 0;
+        open.push(Reverse((manhattan(from, to), 0, from)));
+
+        while let Some(Reverse((_, distance, pos))) = open.pop() {
+            if pos == to {
+                return Some(trace_route(&parents, from, to));
+            }
+            // A position can be in the heap more than once, only the closest
+            // one counts
+            if distance > distances[pos] {
+                continue;
+            }
+            for next in open_neighbours(maze, pos) {
+                if distance + 1 < distances[next] {
+                    distances[next] = distance + 1;
+                    parents[next] = Some(pos);
+                    open.push(Reverse((
+                        distance + 1 + manhattan(next, to),
+                        distance + 1,
+                        next,
+                    )));
+                }
+            }
+        }
+        None
+    }
+}
+
+impl MazeSolver for WallFollower {
+    fn solve(&self, maze: &Maze, from: MazePos, to: MazePos) -> Option<Vec<MazePos>> {
+        if !is_open(maze, from) || !is_open(maze, to) {
+            return None;
+        }
+
+        // The directions come in pairs, so `index ^ 1` is the way back
+        let mut back = DIRECTIONS.len() - 1;
+        let mut pos = from;
+        let mut route = vec![from];
+        let mut seen = HashSet::new();
+
+        while pos != to {
+            // Walking in the same direction from the same place again means
+            // the solver is going round in a loop
+            if !seen.insert((pos, back)) {
+                return None;
+            }
+
+            let (index, next) = (1..=DIRECTIONS.len())
+                .map(|turn| (back + turn) % DIRECTIONS.len())
+                .find_map(|index| {
+                    let (axis, positive) = DIRECTIONS[index];
+                    step(pos, axis, positive, 1)
+                        .filter(|&next| is_open(maze, next))
+                        .map(|next| (index, next))
+                })?;
+
+            back = index ^ 1;
+            pos = next;
+            route.push(pos);
+        }
+        Some(route)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::maze_lib::maze_gen::{gen_maze_with, seeded_rng};
+    use crate::{Algorithm, MazeCell, PickStrategy};
+
+    const ALGORITHMS: [Algorithm; 5] = [
+        Algorithm::RecursiveBacktracker,
+        Algorithm::Prim,
+        Algorithm::Kruskal,
+        Algorithm::Wilson,
+        Algorithm::GrowingTree(PickStrategy::Mixed(50)),
+    ];
+
+    /// Checks `route` goes from Start to End one open step at a time
+    fn assert_valid_route(maze: &Maze, route: &[MazePos]) {
+        assert_eq!(route.first(), Some(&maze.start_pos()));
+        assert_eq!(route.last(), Some(&maze.end_pos()));
+        for pair in route.windows(2) {
+            assert_eq!(manhattan(pair[0], pair[1]), 1);
+            assert!(is_open(maze, pair[1]));
+        }
+    }
+
+    #[test]
+    fn every_generated_maze_is_solvable() {
+        for (seed, algorithm) in ALGORITHMS.iter().enumerate() {
+            for &shape in &[(9, 9, 9, 3), (7, 5, 9, 5)] {
+                let maze = gen_maze_with(algorithm, shape, &mut seeded_rng(seed as u64));
+
+                let shortest = solve(&maze).unwrap();
+                assert_valid_route(&maze, &shortest);
+
+                // There is only one route in a perfect maze
+                assert_eq!(AStar.solve_maze(&maze), Some(shortest.clone()));
+
+                let followed = WallFollower.solve_maze(&maze).unwrap();
+                assert_valid_route(&maze, &followed);
+                assert!(followed.len() >= shortest.len());
+            }
+        }
+    }
+
+    #[test]
+    fn same_position() {
+        let maze = gen_maze_with(&Algorithm::Prim, (7, 7, 7, 7), &mut seeded_rng(3));
+        let start = maze.start_pos();
+
+        assert_eq!(BreadthFirst.solve(&maze, start, start), Some(vec![start]));
+        assert_eq!(AStar.solve(&maze, start, start), Some(vec![start]));
+        assert_eq!(WallFollower.solve(&maze, start, start), Some(vec![start]));
+    }
+
+    #[test]
+    fn rotations_are_counted() {
+        for &(mode, shape) in &[
+            (MazeMode::FourD, (9, 7, 7, 5)),
+            (MazeMode::ThreeD, (9, 9, 7, 3)),
+        ] {
+            let maze = gen_maze_with(&Algorithm::Wilson, shape, &mut seeded_rng(6));
+            let route =
+                solve_with_rotations(&maze, maze.start_pos(), maze.end_pos(), MazeAxis4::XY, mode)
+                    .unwrap();
+
+            // Each move is a step along the plane or a turn onto the next one
+            for pair in route.windows(2) {
+                let ((pos, plane), (next, next_plane)) = (pair[0], pair[1]);
+                if pos == next {
+                    let (mut forward, mut back) = (plane, plane);
+                    forward.cycle(mode);
+                    back.cycle_back(mode);
+                    assert!(next_plane == forward || next_plane == back);
+                } else {
+                    // Only the axes on show and the depth axis can change
+                    let hidden = plane.hidden_axes().1;
+                    let coord = |pos: MazePos| [pos.0, pos.1, pos.2, pos.3][hidden];
+                    assert_eq!(plane, next_plane);
+                    assert_eq!(coord(pos), coord(next));
+                    assert_eq!(manhattan(pos, next), 1);
+                    assert!(is_open(&maze, next));
+                }
+            }
+
+            let steps = route
+                .windows(2)
+                .filter(|pair| pair[0].0 != pair[1].0)
+                .count();
+            assert_eq!(steps, solve(&maze).unwrap().len() - 1);
+            assert_eq!(par(&maze, mode), Some(route.len() - 1));
+        }
+    }
+
+    #[test]
+    fn straight_along_hidden_axes() {
+        // Z is the depth axis of XY so no turn is needed
+        let mut maze = Maze::new_empty((3, 3, 7, 3));
+        for z in 1..6 {
+            maze.0[(1, 1, z, 1)] = MazeCell::Cell;
+        }
+        maze.0[(1, 1, 1, 1)] = MazeCell::Start;
+        maze.0[(1, 1, 5, 1)] = MazeCell::End;
+        assert_eq!(par(&maze, MazeMode::FourD), Some(4));
+        assert_eq!(par(&maze, MazeMode::ThreeD), Some(4));
+
+        // W needs a turn back from XY onto ZW
+        let mut maze = Maze::new_empty((3, 3, 3, 7));
+        for w in 1..6 {
+            maze.0[(1, 1, 1, w)] = MazeCell::Cell;
+        }
+        maze.0[(1, 1, 1, 1)] = MazeCell::Start;
+        maze.0[(1, 1, 1, 5)] = MazeCell::End;
+        assert_eq!(par(&maze, MazeMode::FourD), Some(5));
+    }
+
+    #[test]
+    fn paths_stay_on_the_slice() {
+        // A U shaped corridor on XY with a short cut through Z
+        let mut maze = Maze::new_empty((7, 5, 5, 3));
+        for &(x, y) in &[
+            (1, 1),
+            (1, 2),
+            (1, 3),
+            (2, 3),
+            (3, 3),
+            (4, 3),
+            (5, 3),
+            (5, 2),
+            (5, 1),
+        ] {
+            maze.0[(x, y, 1, 1)] = MazeCell::Cell;
+        }
+        for z in 1..4 {
+            maze.0[(1, 1, z, 1)] = MazeCell::Cell;
+            maze.0[(5, 1, z, 1)] = MazeCell::Cell;
+        }
+        for x in 1..6 {
+            maze.0[(x, 1, 3, 1)] = MazeCell::Cell;
+        }
+
+        let path = slice_path(&maze, (1, 1, 1, 1), (5, 1, 1, 1), MazeAxis4::XY).unwrap();
+        assert_eq!(path.len(), 8);
+        assert_eq!(path[0], Move::Down);
+        assert_eq!(path[7], Move::Up);
+
+        assert_eq!(
+            slice_path(&maze, (1, 1, 1, 1), (1, 1, 1, 1), MazeAxis4::XY),
+            Some(vec![])
+        );
+        // Walls and cells off the slice can't be walked to
+        assert_eq!(
+            slice_path(&maze, (1, 1, 1, 1), (2, 2, 1, 1), MazeAxis4::XY),
+            None
+        );
+        assert_eq!(
+            slice_path(&maze, (1, 1, 1, 1), (3, 1, 3, 1), MazeAxis4::XY),
+            None
+        );
+    }
+
+    #[test]
+    fn unreachable() {
+        let mut maze = gen_maze_with(&Algorithm::Kruskal, (7, 7, 7, 3), &mut seeded_rng(4));
+        let end = maze.end_pos();
+
+        // Seal the end in
+        let around_end: Vec<_> = open_neighbours(&maze, end).collect();
+        for next in around_end {
+            maze.0[next] = MazeCell::Wall;
+        }
+
+        assert_eq!(solve(&maze), None);
+        assert_eq!(AStar.solve_maze(&maze), None);
+        assert_eq!(WallFollower.solve_maze(&maze), None);
+        assert_eq!(shortest_path(&maze, (0, 0, 0, 0), end), None);
+    }
+}
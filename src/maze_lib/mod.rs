@@ -1,7 +1,14 @@
 //! Contains all the maze components
 
+pub mod maze_algorithms;
+pub mod maze_analysis;
 pub mod maze_base;
+pub mod maze_file;
 pub mod maze_gen;
+pub mod maze_move;
+pub mod maze_placement;
+pub mod maze_solve;
 
-/// This reperesents the size of the maze
-pub(crate) const MAZE_SIZE: usize = 8;
\ No newline at end of file
+/// This reperesents the size of the maze along each axis when no size is
+/// chosen
+pub(crate) const DEFAULT_MAZE_SIZE: usize = 8;